@@ -0,0 +1,211 @@
+//! abi.rs - Minimal Solidity ABI helpers for on-chain reads
+//!
+//! Hex encoding, function selectors and word decoding for the
+//! handful of view functions the engine calls (UniswapV2 pairs, ERC-20)
+
+use primitive_types::U256;
+use thiserror::Error;
+
+/// Size of a single ABI word in bytes
+pub const WORD_SIZE: usize = 32;
+
+/// `getReserves()` on UniswapV2-style pairs
+pub const SELECTOR_GET_RESERVES: [u8; 4] = [0x09, 0x02, 0xf1, 0xac];
+
+/// `token0()` on UniswapV2-style pairs
+pub const SELECTOR_TOKEN0: [u8; 4] = [0x0d, 0xfe, 0x16, 0x81];
+
+/// `token1()` on UniswapV2-style pairs
+pub const SELECTOR_TOKEN1: [u8; 4] = [0xd2, 0x12, 0x20, 0xa7];
+
+/// ERC-20 `decimals()`
+pub const SELECTOR_DECIMALS: [u8; 4] = [0x31, 0x3c, 0xe5, 0x67];
+
+/// ERC-20 `symbol()`
+pub const SELECTOR_SYMBOL: [u8; 4] = [0x95, 0xd8, 0x9b, 0x41];
+
+/// ERC-20 `name()`
+pub const SELECTOR_NAME: [u8; 4] = [0x06, 0xfd, 0xde, 0x03];
+
+/// Errors raised while encoding or decoding ABI data
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum AbiError {
+    #[error("invalid hex string: {0}")]
+    InvalidHex(String),
+
+    #[error("return data too short: expected at least {expected} bytes, got {actual}")]
+    TooShort { expected: usize, actual: usize },
+
+    #[error("value does not fit in {0}")]
+    Overflow(&'static str),
+
+    #[error("invalid address: {0}")]
+    InvalidAddress(String),
+}
+
+/// Encode bytes as a 0x-prefixed lowercase hex string
+pub fn encode_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(2 + bytes.len() * 2);
+    out.push_str("0x");
+    for b in bytes {
+        out.push_str(&format!("{:02x}", b));
+    }
+    out
+}
+
+/// Decode a hex string (with or without 0x prefix) into bytes
+pub fn decode_hex(s: &str) -> Result<Vec<u8>, AbiError> {
+    let digits = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+    if !digits.len().is_multiple_of(2) {
+        return Err(AbiError::InvalidHex(s.to_string()));
+    }
+
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16)
+                .map_err(|_| AbiError::InvalidHex(s.to_string()))
+        })
+        .collect()
+}
+
+/// Parse a JSON-RPC hex quantity (e.g. "0x1a") into a u64
+pub fn parse_quantity(s: &str) -> Result<u64, AbiError> {
+    let digits = s.strip_prefix("0x").unwrap_or(s);
+    if digits.is_empty() {
+        return Err(AbiError::InvalidHex(s.to_string()));
+    }
+    u64::from_str_radix(digits, 16).map_err(|_| AbiError::InvalidHex(s.to_string()))
+}
+
+/// Format a u64 as a JSON-RPC hex quantity
+pub fn format_quantity(value: u64) -> String {
+    format!("0x{:x}", value)
+}
+
+/// Return the `index`-th 32-byte word of ABI-encoded data
+pub fn word(data: &[u8], index: usize) -> Result<&[u8], AbiError> {
+    let start = index * WORD_SIZE;
+    let end = start + WORD_SIZE;
+    if data.len() < end {
+        return Err(AbiError::TooShort { expected: end, actual: data.len() });
+    }
+    Ok(&data[start..end])
+}
+
+/// Decode a word as an unsigned integer that must fit in a u128
+pub fn decode_u128(word: &[u8]) -> Result<u128, AbiError> {
+    if word[..16].iter().any(|b| *b != 0) {
+        return Err(AbiError::Overflow("u128"));
+    }
+    let mut buf = [0u8; 16];
+    buf.copy_from_slice(&word[16..32]);
+    Ok(u128::from_be_bytes(buf))
+}
+
+/// Decode a word as a full 256-bit unsigned integer
+pub fn decode_u256(word: &[u8]) -> U256 {
+    U256::from_big_endian(&word[..WORD_SIZE])
+}
+
+/// Decode a word as a u8 (e.g. ERC-20 decimals)
+pub fn decode_u8(word: &[u8]) -> Result<u8, AbiError> {
+    let value = decode_u128(word)?;
+    u8::try_from(value).map_err(|_| AbiError::Overflow("u8"))
+}
+
+/// Decode a word as an address, returned as a 0x-prefixed lowercase hex string
+pub fn decode_address(word: &[u8]) -> Result<String, AbiError> {
+    if word[..12].iter().any(|b| *b != 0) {
+        return Err(AbiError::InvalidAddress(encode_hex(word)));
+    }
+    Ok(encode_hex(&word[12..32]))
+}
+
+/// Decode a string return value, accepting both the standard dynamic
+/// `string` encoding and the legacy `bytes32` encoding used by older tokens
+pub fn decode_string(data: &[u8]) -> Result<String, AbiError> {
+    if data.len() == WORD_SIZE {
+        let end = data.iter().position(|b| *b == 0).unwrap_or(WORD_SIZE);
+        return Ok(String::from_utf8_lossy(&data[..end]).into_owned());
+    }
+
+    let offset = usize::try_from(decode_u128(word(data, 0)?)?)
+        .map_err(|_| AbiError::Overflow("usize"))?;
+    let len_word = data
+        .get(offset..offset + WORD_SIZE)
+        .ok_or(AbiError::TooShort { expected: offset + WORD_SIZE, actual: data.len() })?;
+    let len = usize::try_from(decode_u128(len_word)?).map_err(|_| AbiError::Overflow("usize"))?;
+
+    let start = offset + WORD_SIZE;
+    let bytes = data
+        .get(start..start + len)
+        .ok_or(AbiError::TooShort { expected: start + len, actual: data.len() })?;
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// Encode an address into a left-padded ABI word
+pub fn encode_address(address: &str) -> Result<[u8; WORD_SIZE], AbiError> {
+    let bytes = decode_hex(address)?;
+    if bytes.len() != 20 {
+        return Err(AbiError::InvalidAddress(address.to_string()));
+    }
+    let mut out = [0u8; WORD_SIZE];
+    out[12..].copy_from_slice(&bytes);
+    Ok(out)
+}
+
+/// Encode an unsigned integer into a left-padded ABI word
+pub fn encode_u128(value: u128) -> [u8; WORD_SIZE] {
+    let mut out = [0u8; WORD_SIZE];
+    out[16..].copy_from_slice(&value.to_be_bytes());
+    out
+}
+
+/// Encode a 256-bit unsigned integer as a word
+pub fn encode_u256(value: U256) -> [u8; WORD_SIZE] {
+    let mut out = [0u8; WORD_SIZE];
+    value.to_big_endian(&mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_roundtrip() {
+        let bytes = vec![0x00, 0xab, 0xff];
+        assert_eq!(encode_hex(&bytes), "0x00abff");
+        assert_eq!(decode_hex("0x00abff").unwrap(), bytes);
+        assert!(decode_hex("0xabc").is_err());
+    }
+
+    #[test]
+    fn test_decode_address_and_uint() {
+        let addr = "0xea32a96608495e54156ae48931a7c20f0dcc1a21";
+        let encoded = encode_address(addr).unwrap();
+        assert_eq!(decode_address(&encoded).unwrap(), addr);
+        assert_eq!(decode_u128(&encode_u128(123456789)).unwrap(), 123456789);
+        assert_eq!(decode_u256(&encode_u256(U256::MAX)), U256::MAX);
+        assert_eq!(decode_u256(&encode_u128(7)), U256::from(7));
+        assert_eq!(parse_quantity("0x1a").unwrap(), 26);
+    }
+
+    #[test]
+    fn test_decode_string_variants() {
+        // Dynamic string "USDC"
+        let mut dynamic = Vec::new();
+        dynamic.extend_from_slice(&encode_u128(32));
+        dynamic.extend_from_slice(&encode_u128(4));
+        let mut tail = [0u8; WORD_SIZE];
+        tail[..4].copy_from_slice(b"USDC");
+        dynamic.extend_from_slice(&tail);
+        assert_eq!(decode_string(&dynamic).unwrap(), "USDC");
+
+        // Legacy bytes32 "MKR"
+        let mut fixed = [0u8; WORD_SIZE];
+        fixed[..3].copy_from_slice(b"MKR");
+        assert_eq!(decode_string(&fixed).unwrap(), "MKR");
+    }
+}
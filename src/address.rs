@@ -0,0 +1,173 @@
+//! address.rs - Strongly typed EVM addresses
+//!
+//! Address holds the 20 raw bytes, so comparisons and map lookups
+//! no longer depend on the case an address was written in. Parsing rejects
+//! anything that is not 40 hex digits and mixed-case input whose EIP-55
+//! checksum does not match; display and serde use the checksummed form.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+use tiny_keccak::{Hasher, Keccak};
+
+/// Errors raised while parsing an address
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum AddressError {
+    #[error("address {0} must be 40 hex digits")]
+    InvalidLength(String),
+
+    #[error("address {0} contains non-hex characters")]
+    InvalidHex(String),
+
+    #[error("address {0} does not match its EIP-55 checksum {1}")]
+    InvalidChecksum(String, String),
+}
+
+/// A 20-byte EVM address
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Address([u8; 20]);
+
+impl Address {
+    /// The zero address
+    pub const ZERO: Address = Address([0u8; 20]);
+
+    pub const fn new(bytes: [u8; 20]) -> Self {
+        Address(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 20] {
+        &self.0
+    }
+
+    /// 0x-prefixed lowercase hex, as used in JSON-RPC and API paths
+    pub fn to_lowercase_hex(&self) -> String {
+        let mut out = String::with_capacity(42);
+        out.push_str("0x");
+        for byte in self.0 {
+            out.push_str(&format!("{:02x}", byte));
+        }
+        out
+    }
+
+    /// 0x-prefixed EIP-55 mixed-case hex
+    pub fn to_checksum(&self) -> String {
+        let lower = self.to_lowercase_hex();
+        let mut hash = [0u8; 32];
+        let mut keccak = Keccak::v256();
+        keccak.update(&lower.as_bytes()[2..]);
+        keccak.finalize(&mut hash);
+
+        let mut out = String::with_capacity(42);
+        out.push_str("0x");
+        for (i, c) in lower[2..].chars().enumerate() {
+            let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+            out.push(if nibble >= 8 { c.to_ascii_uppercase() } else { c });
+        }
+        out
+    }
+}
+
+impl FromStr for Address {
+    type Err = AddressError;
+
+    /// Parse 40 hex digits with an optional 0x prefix. All-lowercase and
+    /// all-uppercase input is accepted as is; mixed case must be checksummed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+        if digits.len() != 40 {
+            return Err(AddressError::InvalidLength(s.to_string()));
+        }
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(AddressError::InvalidHex(s.to_string()));
+        }
+
+        let mut bytes = [0u8; 20];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&digits[2 * i..2 * i + 2], 16)
+                .map_err(|_| AddressError::InvalidHex(s.to_string()))?;
+        }
+        let address = Address(bytes);
+
+        let has_lower = digits.chars().any(|c| c.is_ascii_lowercase());
+        let has_upper = digits.chars().any(|c| c.is_ascii_uppercase());
+        if has_lower && has_upper {
+            let checksum = address.to_checksum();
+            if checksum[2..] != *digits {
+                return Err(AddressError::InvalidChecksum(s.to_string(), checksum));
+            }
+        }
+        Ok(address)
+    }
+}
+
+impl From<[u8; 20]> for Address {
+    fn from(bytes: [u8; 20]) -> Self {
+        Address(bytes)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_checksum())
+    }
+}
+
+impl fmt::Debug for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Address({})", self.to_checksum())
+    }
+}
+
+impl Serialize for Address {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_checksum())
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum_roundtrip() {
+        // Reference vectors from EIP-55
+        for expected in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            let address: Address = expected.parse().unwrap();
+            assert_eq!(address.to_string(), expected);
+            assert_eq!(expected.to_lowercase().parse::<Address>().unwrap(), address);
+            assert_eq!(expected.to_uppercase()[2..].parse::<Address>().unwrap(), address);
+        }
+
+        let json = serde_json::to_string(&Address::ZERO).unwrap();
+        assert_eq!(json, "\"0x0000000000000000000000000000000000000000\"");
+        assert_eq!(serde_json::from_str::<Address>(&json).unwrap(), Address::ZERO);
+    }
+
+    #[test]
+    fn test_rejects_malformed_addresses() {
+        assert!(matches!("0x123".parse::<Address>(), Err(AddressError::InvalidLength(_))));
+        assert!(matches!(
+            "0xzzzzb6053f3e94c9b9a09f33669435e7ef1beaed".parse::<Address>(),
+            Err(AddressError::InvalidHex(_))
+        ));
+        // One letter's case flipped from the checksummed form
+        assert!(matches!(
+            "0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".parse::<Address>(),
+            Err(AddressError::InvalidChecksum(_, _))
+        ));
+        assert!(serde_json::from_str::<Address>("\"0x123\"").is_err());
+    }
+}
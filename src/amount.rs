@@ -0,0 +1,304 @@
+//! amount.rs - Raw integer token amounts
+//!
+//! TokenAmount holds an amount in a token's base units as a U256,
+//! together with the token's decimals. Conversions to and from human
+//! `Decimal` values are exact or fail, and arithmetic is checked, so reserve
+//! math and calldata encoding never silently lose precision. Display values
+//! (reserves, prices) use the explicitly lossy conversions instead.
+
+use primitive_types::{U256, U512};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use thiserror::Error;
+
+/// Largest scale a `Decimal` can carry
+const MAX_DECIMAL_SCALE: u32 = 28;
+
+/// Decimal places kept when dividing two amounts
+const RATIO_PLACES: u32 = 28;
+
+/// Errors raised by TokenAmount conversions and arithmetic
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum AmountError {
+    #[error("amount {0} is negative")]
+    Negative(Decimal),
+
+    #[error("amount {value} has more than {decimals} decimal places")]
+    TooPrecise { value: Decimal, decimals: u8 },
+
+    #[error("{raw} base units with {decimals} decimals do not fit in a Decimal")]
+    Unrepresentable { raw: U256, decimals: u8 },
+
+    #[error("cannot combine amounts with {0} and {1} decimals")]
+    DecimalsMismatch(u8, u8),
+
+    #[error("amount overflows 256 bits")]
+    Overflow,
+
+    #[error("amount underflows zero")]
+    Underflow,
+
+    #[error("division by zero")]
+    DivisionByZero,
+}
+
+/// An amount of a token in its base units
+///
+/// Two amounts are equal only if both the raw value and the decimals match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TokenAmount {
+    #[serde(serialize_with = "serialize_u256", deserialize_with = "deserialize_u256")]
+    raw: U256,
+    decimals: u8,
+}
+
+impl TokenAmount {
+    pub fn new(raw: U256, decimals: u8) -> Self {
+        TokenAmount { raw, decimals }
+    }
+
+    pub fn zero(decimals: u8) -> Self {
+        Self::new(U256::zero(), decimals)
+    }
+
+    /// Exact base-unit amount for a human value (e.g. 1.5 with 6 decimals
+    /// is 1_500_000); fails if the value has more places than the token
+    pub fn from_decimal(value: Decimal, decimals: u8) -> Result<Self, AmountError> {
+        if value.is_sign_negative() && !value.is_zero() {
+            return Err(AmountError::Negative(value));
+        }
+        let value_normalized = value.normalize();
+        let scale = value_normalized.scale();
+        if scale > u32::from(decimals) {
+            return Err(AmountError::TooPrecise { value, decimals });
+        }
+
+        let mantissa = U256::from(value_normalized.mantissa().unsigned_abs());
+        let raw = mantissa
+            .checked_mul(pow10(u32::from(decimals) - scale)?)
+            .ok_or(AmountError::Overflow)?;
+        Ok(Self::new(raw, decimals))
+    }
+
+    /// Like `from_decimal`, but rounds excess decimal places down instead of
+    /// failing, e.g. for reserves reported as floating-point values
+    pub fn from_decimal_floor(value: Decimal, decimals: u8) -> Result<Self, AmountError> {
+        let truncated = value.round_dp_with_strategy(u32::from(decimals), RoundingStrategy::ToZero);
+        Self::from_decimal(truncated, decimals)
+    }
+
+    pub fn raw(&self) -> U256 {
+        self.raw
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.raw.is_zero()
+    }
+
+    /// Exact human value; fails if it needs more than 96 bits of mantissa
+    /// or 28 decimal places
+    pub fn to_decimal(&self) -> Result<Decimal, AmountError> {
+        let max_mantissa = U256::from(u128::MAX >> 32);
+        let ten = U256::from(10);
+        let (mut raw, mut scale) = (self.raw, u32::from(self.decimals));
+
+        // Trailing zeros can be dropped without changing the value
+        while (scale > MAX_DECIMAL_SCALE || raw > max_mantissa) && scale > 0 && (raw % ten).is_zero() && !raw.is_zero() {
+            raw /= ten;
+            scale -= 1;
+        }
+        if raw.is_zero() {
+            return Ok(Decimal::ZERO);
+        }
+        if scale > MAX_DECIMAL_SCALE || raw > max_mantissa {
+            return Err(AmountError::Unrepresentable { raw: self.raw, decimals: self.decimals });
+        }
+
+        Ok(Decimal::from_i128_with_scale(raw.as_u128() as i128, scale))
+    }
+
+    /// Human value with the digits a Decimal cannot hold rounded off; None
+    /// only beyond `Decimal::MAX`. For display and pricing, never for
+    /// amounts traded.
+    pub fn to_decimal_lossy(&self) -> Option<Decimal> {
+        decimal_lossy(U512::from(self.raw), i64::from(self.decimals))
+    }
+
+    /// Human value of `self / denominator` (e.g. quote reserve per base
+    /// reserve), computed in integers and rounded like `to_decimal_lossy`;
+    /// None if `denominator` is zero or the ratio exceeds `Decimal::MAX`
+    pub fn ratio_lossy(&self, denominator: &TokenAmount) -> Option<Decimal> {
+        if denominator.raw.is_zero() {
+            return None;
+        }
+        let quotient = self.raw.full_mul(pow10_u256(RATIO_PLACES)) / U512::from(denominator.raw);
+        let scale = i64::from(RATIO_PLACES) + i64::from(self.decimals) - i64::from(denominator.decimals);
+        decimal_lossy(quotient, scale)
+    }
+
+    pub fn checked_add(self, other: TokenAmount) -> Result<Self, AmountError> {
+        self.same_decimals(&other)?;
+        let raw = self.raw.checked_add(other.raw).ok_or(AmountError::Overflow)?;
+        Ok(Self::new(raw, self.decimals))
+    }
+
+    pub fn checked_sub(self, other: TokenAmount) -> Result<Self, AmountError> {
+        self.same_decimals(&other)?;
+        let raw = self.raw.checked_sub(other.raw).ok_or(AmountError::Underflow)?;
+        Ok(Self::new(raw, self.decimals))
+    }
+
+    /// `self * numerator / denominator`, rounded down, with a 512-bit
+    /// intermediate so the product cannot overflow
+    pub fn mul_div(self, numerator: U256, denominator: U256) -> Result<Self, AmountError> {
+        if denominator.is_zero() {
+            return Err(AmountError::DivisionByZero);
+        }
+        let quotient = self.raw.full_mul(numerator) / U512::from(denominator);
+        let raw = U256::try_from(quotient).map_err(|_| AmountError::Overflow)?;
+        Ok(Self::new(raw, self.decimals))
+    }
+
+    fn same_decimals(&self, other: &TokenAmount) -> Result<(), AmountError> {
+        if self.decimals != other.decimals {
+            return Err(AmountError::DecimalsMismatch(self.decimals, other.decimals));
+        }
+        Ok(())
+    }
+}
+
+/// 10^exp, failing once it no longer fits in 256 bits
+fn pow10(exp: u32) -> Result<U256, AmountError> {
+    U256::from(10).checked_pow(U256::from(exp)).ok_or(AmountError::Overflow)
+}
+
+fn pow10_u256(exp: u32) -> U256 {
+    U256::from(10).pow(U256::from(exp))
+}
+
+/// `raw / 10^scale` as a Decimal, rounding down past the digits it can
+/// hold; None beyond `Decimal::MAX`
+fn decimal_lossy(mut raw: U512, mut scale: i64) -> Option<Decimal> {
+    let max_mantissa = U512::from(u128::MAX >> 32);
+    let ten = U512::from(10);
+
+    while scale < 0 {
+        raw = raw.checked_mul(ten)?;
+        scale += 1;
+    }
+    while (scale > i64::from(MAX_DECIMAL_SCALE) || raw > max_mantissa) && scale > 0 {
+        raw /= ten;
+        scale -= 1;
+    }
+    (raw <= max_mantissa).then(|| Decimal::from_i128_with_scale(raw.low_u128() as i128, scale as u32))
+}
+
+impl fmt::Display for TokenAmount {
+    /// Exact human value, without trailing zeros
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.raw.to_string();
+        let decimals = usize::from(self.decimals);
+        if decimals == 0 {
+            return f.write_str(&digits);
+        }
+
+        let padded = format!("{:0>width$}", digits, width = decimals + 1);
+        let (int, frac) = padded.split_at(padded.len() - decimals);
+        let frac = frac.trim_end_matches('0');
+        if frac.is_empty() {
+            f.write_str(int)
+        } else {
+            write!(f, "{}.{}", int, frac)
+        }
+    }
+}
+
+fn serialize_u256<S: Serializer>(value: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
+
+fn deserialize_u256<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    U256::from_dec_str(&s).map_err(|e| serde::de::Error::custom(format!("invalid amount {}: {:?}", s, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_decimal_roundtrip_is_exact() {
+        let amount = TokenAmount::from_decimal(dec!(1850.123456), 6).unwrap();
+        assert_eq!(amount.raw(), U256::from(1_850_123_456u64));
+        assert_eq!(amount.to_decimal().unwrap(), dec!(1850.123456));
+        assert_eq!(amount.to_string(), "1850.123456");
+
+        // 18-decimal amounts beyond u64 survive untouched
+        let wei = TokenAmount::from_decimal(dec!(123456789.000000000000000001), 18).unwrap();
+        assert_eq!(wei.raw(), U256::from_dec_str("123456789000000000000000001").unwrap());
+        assert_eq!(wei.to_decimal().unwrap(), dec!(123456789.000000000000000001));
+
+        assert_eq!(
+            TokenAmount::from_decimal(dec!(0.0000001), 6),
+            Err(AmountError::TooPrecise { value: dec!(0.0000001), decimals: 6 })
+        );
+        assert!(matches!(TokenAmount::from_decimal(dec!(-1), 6), Err(AmountError::Negative(_))));
+        assert_eq!(TokenAmount::from_decimal_floor(dec!(0.0000019), 6).unwrap().raw(), U256::one());
+    }
+
+    #[test]
+    fn test_full_range_values_fail_instead_of_truncating() {
+        let max = TokenAmount::new(U256::MAX, 18);
+        assert!(matches!(max.to_decimal(), Err(AmountError::Unrepresentable { .. })));
+        assert_eq!(max.to_string().len(), 79);
+
+        // Trailing zeros are dropped to fit a Decimal's scale
+        let round = TokenAmount::new(U256::from(10).pow(U256::from(30)), 30);
+        assert_eq!(round.to_decimal().unwrap(), Decimal::ONE);
+
+        let json = serde_json::to_string(&max).unwrap();
+        assert_eq!(serde_json::from_str::<TokenAmount>(&json).unwrap(), max);
+    }
+
+    #[test]
+    fn test_lossy_conversions_round_off_digits_only() {
+        // 10^12 tokens plus one wei needs 31 digits of mantissa
+        let reserve = TokenAmount::new(U256::from(10).pow(U256::from(30)) + 1, 18);
+        assert!(reserve.to_decimal().is_err());
+        assert_eq!(reserve.to_decimal_lossy(), Some(dec!(1000000000000)));
+        assert_eq!(TokenAmount::new(U256::MAX, 0).to_decimal_lossy(), None);
+
+        // 1850 USDC (6 decimals) per WETH (18 decimals)
+        let usdc = TokenAmount::from_decimal(dec!(1850000), 6).unwrap();
+        let weth = TokenAmount::from_decimal(dec!(1000), 18).unwrap();
+        assert_eq!(usdc.ratio_lossy(&weth), Some(dec!(1850)));
+        assert_eq!(weth.ratio_lossy(&usdc).unwrap().round_dp(10), dec!(0.0005405405));
+        assert_eq!(usdc.ratio_lossy(&TokenAmount::zero(18)), None);
+        assert_eq!(TokenAmount::new(U256::MAX, 0).ratio_lossy(&TokenAmount::new(U256::one(), 0)), None);
+
+        // Huge reserves on both sides still give their exact ratio
+        let huge = TokenAmount::new(U256::MAX / 4, 18);
+        assert_eq!(TokenAmount::new(U256::MAX / 4 * 3, 18).ratio_lossy(&huge), Some(dec!(3)));
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let a = TokenAmount::from_decimal(dec!(1.5), 6).unwrap();
+        let b = TokenAmount::from_decimal(dec!(0.25), 6).unwrap();
+        assert_eq!(a.checked_add(b).unwrap().to_decimal().unwrap(), dec!(1.75));
+        assert_eq!(b.checked_sub(a), Err(AmountError::Underflow));
+        assert_eq!(a.checked_add(TokenAmount::zero(18)), Err(AmountError::DecimalsMismatch(6, 18)));
+        assert_eq!(TokenAmount::new(U256::MAX, 0).checked_add(TokenAmount::new(U256::one(), 0)), Err(AmountError::Overflow));
+
+        // The intermediate product exceeds 256 bits but the result does not
+        let big = TokenAmount::new(U256::MAX / 2, 18);
+        assert_eq!(big.mul_div(U256::from(4), U256::from(8)).unwrap().raw(), U256::MAX / 4);
+        assert_eq!(a.mul_div(U256::one(), U256::zero()), Err(AmountError::DivisionByZero));
+    }
+}
@@ -0,0 +1,161 @@
+//! chain.rs - EVM chains the engine can run against
+//!
+//! Chain describes a network by id, with its native gas token, wrapped
+//! native token, block time and known infrastructure contracts (Multicall3,
+//! gas price oracle). Metis Andromeda is built in; further chains come from
+//! the config file. Exchanges and tokens refer to their chain by id, and
+//! feeds take the Chain to find its Multicall3 and wrapped native token.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::address::Address;
+use crate::multicall::MULTICALL3_ADDRESS;
+
+/// Chain id of Metis Andromeda
+pub const METIS_CHAIN_ID: u64 = 1088;
+
+/// WMETIS on Metis Andromeda
+pub const METIS_WRAPPED_NATIVE: &str = "0x75cb093E4D61d2A2e65D8e0BBb01DE8d89b53481";
+
+/// OVM_GasPriceOracle predeploy on Metis Andromeda
+pub const METIS_GAS_PRICE_ORACLE: &str = "0x420000000000000000000000000000000000000F";
+
+/// The token a chain charges gas in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NativeCurrency {
+    pub symbol: String,
+    pub name: String,
+    pub decimals: u8,
+}
+
+/// An EVM network and the contracts the engine relies on there
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chain {
+    /// EIP-155 chain id
+    pub id: u64,
+    pub name: String,
+    pub native_currency: NativeCurrency,
+    /// ERC-20 wrapper of the native currency (e.g. WMETIS, WETH)
+    pub wrapped_native: Address,
+    /// Average time between blocks, in milliseconds
+    pub block_time_ms: u64,
+    /// Multicall3 deployment (None if the chain has none)
+    #[serde(default)]
+    pub multicall3: Option<Address>,
+    /// Contract quoting the L1 data fee on rollups (None on L1s)
+    #[serde(default)]
+    pub gas_price_oracle: Option<Address>,
+}
+
+impl Chain {
+    /// Metis Andromeda
+    pub fn metis() -> Self {
+        let address = |address: &str| address.parse().expect("known chain address is checksummed");
+        Chain {
+            id: METIS_CHAIN_ID,
+            name: "Metis Andromeda".to_string(),
+            native_currency: NativeCurrency {
+                symbol: "METIS".to_string(),
+                name: "Metis Token".to_string(),
+                decimals: 18,
+            },
+            wrapped_native: address(METIS_WRAPPED_NATIVE),
+            block_time_ms: 4_000,
+            multicall3: Some(address(MULTICALL3_ADDRESS)),
+            gas_price_oracle: Some(address(METIS_GAS_PRICE_ORACLE)),
+        }
+    }
+
+    pub fn block_time(&self) -> Duration {
+        Duration::from_millis(self.block_time_ms)
+    }
+}
+
+/// Chains known to the engine, keyed by chain id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainRegistry {
+    chains: BTreeMap<u64, Chain>,
+}
+
+impl ChainRegistry {
+    /// A registry holding the built-in chains (Metis Andromeda)
+    pub fn new() -> Self {
+        ChainRegistry {
+            chains: BTreeMap::from([(METIS_CHAIN_ID, Chain::metis())]),
+        }
+    }
+
+    /// Add chains, replacing built-in entries with the same id
+    pub fn with_chains(mut self, chains: impl IntoIterator<Item = Chain>) -> Self {
+        for chain in chains {
+            self.chains.insert(chain.id, chain);
+        }
+        self
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Chain> {
+        self.chains.get(&id)
+    }
+
+    /// Look a chain up by name, ignoring case
+    pub fn by_name(&self, name: &str) -> Option<&Chain> {
+        self.chains.values().find(|c| c.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Known chains in id order
+    pub fn iter(&self) -> impl Iterator<Item = &Chain> {
+        self.chains.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chains.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chains.is_empty()
+    }
+}
+
+impl Default for ChainRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_has_metis_and_accepts_configured_chains() {
+        let metis = Chain::metis();
+        assert_eq!(metis.native_currency.symbol, "METIS");
+        assert_eq!(metis.multicall3, Some(MULTICALL3_ADDRESS.parse().unwrap()));
+        assert_eq!(metis.gas_price_oracle, Some("0x420000000000000000000000000000000000000f".parse().unwrap()));
+        assert_eq!(metis.block_time(), Duration::from_secs(4));
+
+        let arbitrum: Chain = serde_json::from_str(r#"{
+            "id": 42161,
+            "name": "Arbitrum One",
+            "native_currency": { "symbol": "ETH", "name": "Ether", "decimals": 18 },
+            "wrapped_native": "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1",
+            "block_time_ms": 250
+        }"#).unwrap();
+        assert_eq!(arbitrum.multicall3, None);
+        assert_eq!(arbitrum.gas_price_oracle, None);
+
+        let registry = ChainRegistry::new().with_chains([arbitrum.clone()]);
+        assert_eq!(registry.len(), 2);
+        assert_eq!(registry.get(METIS_CHAIN_ID), Some(&metis));
+        assert_eq!(registry.by_name("arbitrum one"), Some(&arbitrum));
+        assert!(registry.get(1).is_none());
+
+        // Configured entries replace built-in ones
+        let slow_metis = Chain { block_time_ms: 10_000, multicall3: None, ..Chain::metis() };
+        let registry = registry.with_chains([slow_metis]);
+        assert_eq!(registry.get(METIS_CHAIN_ID).unwrap().block_time_ms, 10_000);
+        assert_eq!(registry.get(METIS_CHAIN_ID).unwrap().multicall3, None);
+    }
+}
@@ -0,0 +1,194 @@
+//! config.rs - Runtime configuration for the arbitrage bot
+//!
+//! Loaded from a JSON file named by `FURUCOMBO_CONFIG`; every section falls
+//! back to the Metis defaults so an empty `{}` file is valid. Chains beyond
+//! the built-in Metis Andromeda are declared under `chains`, the detection
+//! strategies to run under `strategies`, and pools to read on-chain (and
+//! cross-check against DEX Screener) under `onchain`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::filter::PairFilter;
+use crate::price_feed::{DexScreenerConfig, PoolConfig, DEFAULT_DIVERGENCE_TOLERANCE_BPS};
+use crate::sizing::CapitalLimits;
+use crate::strategy::{StrategyConfig, StrategyRegistry};
+use crate::chain::{Chain, ChainRegistry, METIS_CHAIN_ID};
+
+/// Environment variable holding the path of the JSON config file
+pub const CONFIG_PATH_ENV: &str = "FURUCOMBO_CONFIG";
+
+/// Errors raised while loading the config file
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read config {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("invalid config {path}: {source}")]
+    Json {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+/// Top-level bot configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// DEX Screener feed: chain, DEX allowlist and discovery
+    pub dex_screener: DexScreenerConfig,
+    /// Pairs kept by the feed and considered by detection
+    pub filter: PairFilter,
+    /// Uniswap-format token list of canonical tokens, merged with the
+    /// built-in Metis tokens
+    pub token_list: Option<TokenListSource>,
+    /// Chains added to (or replacing) the built-in Metis Andromeda
+    pub chains: Vec<Chain>,
+    /// Most capital a single route may use, per start token
+    pub capital: CapitalLimits,
+    /// Strategies run on every scan, in order; empty runs the built-in set
+    pub strategies: Vec<StrategyConfig>,
+    /// Pools read from their contracts and merged with DEX Screener; None
+    /// runs on DEX Screener alone
+    pub onchain: Option<OnChainConfig>,
+}
+
+/// Pools read on-chain and how their prices are reconciled with DEX Screener
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OnChainConfig {
+    pub pools: Vec<PoolConfig>,
+    /// JSON-RPC endpoint; defaults to the DEX Screener feed's `rpc_url`
+    #[serde(default)]
+    pub rpc_url: Option<String>,
+    /// Sources that must agree on a pool's price for it to be scanned;
+    /// both DEX Screener and the chain by default
+    #[serde(default = "default_quorum")]
+    pub quorum: usize,
+    /// Largest deviation from the median price still counted as agreeing
+    #[serde(default = "default_tolerance_bps")]
+    pub tolerance_bps: u32,
+}
+
+fn default_quorum() -> usize {
+    2
+}
+
+fn default_tolerance_bps() -> u32 {
+    DEFAULT_DIVERGENCE_TOLERANCE_BPS
+}
+
+/// Location of a token list and the chain whose entries to import
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenListSource {
+    pub path: PathBuf,
+    #[serde(default = "default_chain_id")]
+    pub chain_id: u64,
+    /// Other symbols for listed tokens, alias -> listed symbol
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+}
+
+fn default_chain_id() -> u64 {
+    METIS_CHAIN_ID
+}
+
+impl AppConfig {
+    /// Load a config file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let raw = std::fs::read_to_string(path)
+            .map_err(|source| ConfigError::Io { path: path.to_path_buf(), source })?;
+        serde_json::from_str(&raw)
+            .map_err(|source| ConfigError::Json { path: path.to_path_buf(), source })
+    }
+
+    /// Built-in chains plus those declared in the config
+    pub fn chain_registry(&self) -> ChainRegistry {
+        ChainRegistry::new().with_chains(self.chains.iter().cloned())
+    }
+
+    /// Chain the DEX Screener feed runs against, if known
+    pub fn chain(&self) -> Option<Chain> {
+        self.chain_registry().get(self.dex_screener.evm_chain_id).cloned()
+    }
+
+    /// DEX Screener settings completed from the chain registry
+    pub fn screener_config(&self) -> DexScreenerConfig {
+        match self.chain() {
+            Some(chain) => self.dex_screener.clone().with_chain(&chain),
+            None => self.dex_screener.clone(),
+        }
+    }
+
+    /// Configured strategies, with sized cycles capped by `capital`
+    pub fn strategy_registry(&self) -> StrategyRegistry {
+        StrategyRegistry::from_config(&self.strategies, &self.capital)
+    }
+
+    /// Load the file named by `FURUCOMBO_CONFIG`, or the defaults if unset
+    pub fn from_env() -> Result<Self, ConfigError> {
+        match std::env::var_os(CONFIG_PATH_ENV) {
+            Some(path) => Self::load(path),
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_feed::DiscoveryStrategy;
+
+    #[test]
+    fn test_load_config_with_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(&path, r#"{
+            "dex_screener": {
+                "chain_id": "metis",
+                "evm_chain_id": 1088,
+                "dex_allowlist": ["netswap", "tethys", "hercules"],
+                "discovery": [{ "type": "search", "terms": ["metis"] }],
+                "rpc_url": "https://andromeda.metis.io/?owner=1088"
+            },
+            "onchain": {
+                "pools": [{ "address": "0x00000000000000000000000000000000000000a1", "exchange": "netswap" }]
+            }
+        }"#).unwrap();
+
+        let config = AppConfig::load(&path).unwrap();
+        assert!(config.dex_screener.allows_dex("hercules"));
+        assert_eq!(
+            config.dex_screener.discovery,
+            vec![DiscoveryStrategy::Search { terms: vec!["metis".to_string()] }]
+        );
+        assert_eq!(config.dex_screener.base_url, DexScreenerConfig::metis().base_url);
+
+        assert_eq!(config.filter, PairFilter::default());
+        assert_eq!(config.chain(), Some(Chain::metis()));
+        assert_eq!(config.strategy_registry().names(), vec!["cross_dex", "two_pool", "triangular"]);
+
+        let onchain = config.onchain.as_ref().unwrap();
+        assert_eq!(onchain.pools[0].exchange, "netswap");
+        assert_eq!((onchain.quorum, onchain.tolerance_bps), (2, DEFAULT_DIVERGENCE_TOLERANCE_BPS));
+        assert_eq!(onchain.rpc_url, None);
+
+        std::fs::write(&path, "{}").unwrap();
+        assert_eq!(AppConfig::load(&path).unwrap(), AppConfig::default());
+
+        // Token crawls also start from the chain's wrapped native token
+        let wmetis = Chain::metis().wrapped_native;
+        let crawls_wmetis = |config: &DexScreenerConfig| config.discovery.iter().any(|d| {
+            matches!(d, DiscoveryStrategy::TokenCrawl { seeds, .. } if seeds.contains(&wmetis))
+        });
+        assert!(!crawls_wmetis(&AppConfig::default().dex_screener));
+        assert!(crawls_wmetis(&AppConfig::default().screener_config()));
+    }
+}
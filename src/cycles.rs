@@ -0,0 +1,376 @@
+//! cycles.rs - Multi-hop arbitrage detection on a token graph
+//!
+//! Every pool contributes two directed edges between its tokens,
+//! weighted by −ln of the fee-adjusted exchange rate, so a profitable cycle
+//! is a negative-weight cycle. A hop-bounded Bellman-Ford from each token
+//! extracts the negative cycles on which it is the smallest token, so each
+//! cycle is found and priced once; cycles that form a valid ArbitrageRoute are executed against pool reserves (or at mid prices
+//! when reserves are unknown) and reported as ArbitrageOpportunity values,
+//! optionally at their profit-maximizing size. Cross-DEX spreads are the
+//! two-hop case; triangular and longer cycles come for free.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+
+use crate::address::Address;
+use crate::amount::TokenAmount;
+use crate::filter::PairFilter;
+use crate::models::{ArbitrageLeg, ArbitrageOpportunity, ArbitrageRoute, TradingPair};
+use crate::simulation::{simulate_route, SimulationError};
+use crate::sizing::{SizingError, TradeSizer};
+
+/// Longest cycle searched for by default
+pub const DEFAULT_MAX_HOPS: usize = 3;
+
+/// Profit (in percent of the input) above which a cycle is reported
+pub const DEFAULT_MIN_PROFIT_PERCENT: Decimal = Decimal::from_parts(1, 0, 0, false, 1);
+
+/// A swap through one pool, as a graph edge between token indices
+struct Edge {
+    from: usize,
+    to: usize,
+    weight: f64,
+    leg: ArbitrageLeg,
+}
+
+/// Directed token graph built from a pair snapshot
+struct TokenGraph {
+    tokens: Vec<Address>,
+    edges: Vec<Edge>,
+    /// Edge indices leaving each token
+    outgoing: Vec<Vec<usize>>,
+}
+
+impl TokenGraph {
+    fn new(pairs: &[&TradingPair]) -> Self {
+        let mut index: HashMap<Address, usize> = HashMap::new();
+        let mut tokens = Vec::new();
+        let mut edges = Vec::new();
+
+        for pair in pairs {
+            for from in [pair.base_token.address, pair.quote_token.address] {
+                let Some(leg) = pair.leg_from(from) else { continue };
+                // -ln(rate) needs a positive rate; a zero price has no edge
+                let Some(rate) = leg.amount_out(Decimal::ONE).to_f64().filter(|r| *r > 0.0) else {
+                    continue;
+                };
+
+                let mut node = |address: Address| {
+                    *index.entry(address).or_insert_with(|| {
+                        tokens.push(address);
+                        tokens.len() - 1
+                    })
+                };
+                let (from, to) = (node(leg.from_token.address), node(leg.to_token.address));
+                edges.push(Edge { from, to, weight: -rate.ln(), leg });
+            }
+        }
+
+        let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); tokens.len()];
+        for (e, edge) in edges.iter().enumerate() {
+            outgoing[edge.from].push(e);
+        }
+
+        TokenGraph { tokens, edges, outgoing }
+    }
+
+    /// Negative cycles of 2 to `max_hops` swaps whose smallest token is
+    /// `source`, with their total weight below `threshold`, as edge indices
+    /// in walk order starting at `source`
+    ///
+    /// A hop-bounded Bellman-Ford from `source`, run once per edge leaving
+    /// it: `dist[hops][token]` is the lightest walk of exactly `hops` swaps
+    /// that starts with that edge, relaxed only out of tokens reached on the
+    /// previous hop and never through tokens smaller than `source`. Each
+    /// closed walk back to `source` below the threshold is extracted from
+    /// the predecessor table, so every cycle is found from one canonical
+    /// start, and distinct cycles sharing `source` are kept apart by their
+    /// first swap. Walks may still revisit a token; callers drop those.
+    fn negative_cycles(&self, source: usize, max_hops: usize, threshold: f64) -> Vec<(f64, Vec<usize>)> {
+        let n = self.tokens.len();
+        let above_source = |token: usize| self.tokens[token] > self.tokens[source];
+
+        let mut cycles = Vec::new();
+        for &first in &self.outgoing[source] {
+            let start = self.edges[first].to;
+            if !above_source(start) {
+                continue;
+            }
+
+            let mut dist = vec![vec![f64::INFINITY; n]; max_hops + 1];
+            let mut pred = vec![vec![usize::MAX; n]; max_hops + 1];
+            dist[1][start] = self.edges[first].weight;
+            pred[1][start] = first;
+
+            for hops in 2..=max_hops {
+                for node in 0..n {
+                    let reached = dist[hops - 1][node];
+                    if node == source || reached.is_infinite() {
+                        continue;
+                    }
+                    for &e in &self.outgoing[node] {
+                        let edge = &self.edges[e];
+                        if edge.to != source && !above_source(edge.to) {
+                            continue;
+                        }
+                        let through = reached + edge.weight;
+                        if through < dist[hops][edge.to] {
+                            dist[hops][edge.to] = through;
+                            pred[hops][edge.to] = e;
+                        }
+                    }
+                }
+
+                if dist[hops][source] < threshold {
+                    let mut walk = Vec::with_capacity(hops);
+                    let mut node = source;
+                    for h in (1..=hops).rev() {
+                        let e = pred[h][node];
+                        walk.push(e);
+                        node = self.edges[e].from;
+                    }
+                    walk.reverse();
+                    cycles.push((dist[hops][source], walk));
+                }
+            }
+        }
+        cycles
+    }
+}
+
+/// Finds profitable cycles of up to `max_hops` swaps across all pools
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleDetector {
+    max_hops: usize,
+    min_profit_percent: Decimal,
+    input_amount: Decimal,
+    start_tokens: Vec<Address>,
+    sizer: Option<TradeSizer>,
+}
+
+impl CycleDetector {
+    /// Cycles of up to `DEFAULT_MAX_HOPS` from any token, priced for one
+    /// unit of the start token
+    pub fn new() -> Self {
+        CycleDetector {
+            max_hops: DEFAULT_MAX_HOPS,
+            min_profit_percent: DEFAULT_MIN_PROFIT_PERCENT,
+            input_amount: Decimal::ONE,
+            start_tokens: Vec::new(),
+            sizer: None,
+        }
+    }
+
+    pub fn with_max_hops(mut self, max_hops: usize) -> Self {
+        self.max_hops = max_hops;
+        self
+    }
+
+    pub fn with_min_profit_percent(mut self, min_profit_percent: Decimal) -> Self {
+        self.min_profit_percent = min_profit_percent;
+        self
+    }
+
+    /// Amount of the start token each cycle is priced for
+    pub fn with_input_amount(mut self, input_amount: Decimal) -> Self {
+        self.input_amount = input_amount;
+        self
+    }
+
+    /// Size cycles with known reserves for maximum profit instead of
+    /// pricing them for the fixed input amount
+    pub fn with_sizer(mut self, sizer: TradeSizer) -> Self {
+        self.sizer = Some(sizer);
+        self
+    }
+
+    /// Only report cycles starting and ending in these tokens (e.g. the
+    /// stablecoins capital is held in); empty allows every token
+    pub fn with_start_tokens(mut self, start_tokens: Vec<Address>) -> Self {
+        self.start_tokens = start_tokens;
+        self
+    }
+
+    /// Profitable cycles in `pairs`, most profitable first
+    ///
+    /// Each cycle is found once, from its smallest token, and priced once
+    /// from that token or, with start tokens set, from the first of them
+    /// along it. `now` is the time staleness is judged against.
+    pub fn find_cycles(
+        &self,
+        pairs: &[TradingPair],
+        filter: &PairFilter,
+        now: DateTime<Utc>,
+    ) -> Vec<ArbitrageOpportunity> {
+        let kept: Vec<&TradingPair> = pairs.iter().filter(|p| filter.check_at(p, now).is_ok()).collect();
+        let graph = TokenGraph::new(&kept);
+
+        // Rate product must beat 1 + min profit, i.e. weight below -ln of it
+        let min_rate = Decimal::ONE + self.min_profit_percent / Decimal::from(100);
+        let threshold = -min_rate.to_f64().unwrap_or(1.0).ln();
+
+        let mut opportunities = Vec::new();
+        for source in 0..graph.tokens.len() {
+            for (_, mut walk) in graph.negative_cycles(source, self.max_hops, threshold) {
+                // Re-express the cycle from its first allowed start token
+                if !self.start_tokens.is_empty() {
+                    let allowed = walk.iter().position(|&e| {
+                        self.start_tokens.contains(&graph.tokens[graph.edges[e].from])
+                    });
+                    let Some(offset) = allowed else { continue };
+                    walk.rotate_left(offset);
+                }
+                opportunities.extend(self.price_walk(&graph, &walk));
+            }
+        }
+
+        opportunities.sort_by_key(|o| std::cmp::Reverse(o.profit_percentage));
+        opportunities
+    }
+
+    /// Build and price a walk, or None if it is not a simple cycle or falls
+    /// short of the minimum profit once executed
+    fn price_walk(&self, graph: &TokenGraph, walk: &[usize]) -> Option<ArbitrageOpportunity> {
+        let legs: Vec<ArbitrageLeg> = walk.iter().map(|&e| graph.edges[e].leg.clone()).collect();
+
+        let mut visited = HashSet::new();
+        if !legs.iter().all(|leg| visited.insert(leg.from_token.address)) {
+            return None;
+        }
+        let route = ArbitrageRoute::with_max_hops(legs, self.max_hops).ok()?;
+
+        // At the optimal size the percentage is lower than at the margin;
+        // any net profit is worth reporting
+        if let Some(sizer) = &self.sizer {
+            match sizer.size(route.clone(), Decimal::ZERO) {
+                Ok(opportunity) => return (opportunity.net_profit > Decimal::ZERO).then_some(opportunity),
+                Err(SizingError::Simulation(SimulationError::MissingReserves(_))) => {}
+                Err(_) => return None,
+            }
+        }
+
+        // Execute against pool reserves where known, else at mid prices
+        let input = TokenAmount::from_decimal_floor(self.input_amount, route.start_token().decimals).ok()?;
+        let opportunity = match simulate_route(&route, input) {
+            Ok(simulation) => simulation.to_opportunity(route, Decimal::ZERO).ok()?,
+            Err(SimulationError::MissingReserves(_)) => {
+                let output = route.amount_out(self.input_amount);
+                let profit = output - self.input_amount;
+                ArbitrageOpportunity::new(route, self.input_amount, output, profit, profit, Decimal::ZERO)
+            }
+            Err(_) => return None,
+        };
+        (opportunity.profit_percentage > self.min_profit_percent).then_some(opportunity)
+    }
+}
+
+impl Default for CycleDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Exchange, Pool, Token};
+    use crate::price_feed::{MockPriceFeed, PriceFeed};
+    use crate::test_util::{address, token};
+    use rust_decimal_macros::dec;
+
+    fn pair(base: &Token, quote: &Token, pool: u8, price: Decimal) -> TradingPair {
+        let exchange = Exchange::new("netswap", crate::chain::METIS_CHAIN_ID);
+        let pool = Pool::new(address(pool), exchange, base.clone(), quote.clone());
+        TradingPair::new(base.clone(), quote.clone(), pool, price, dec!(1000000), dec!(1000), dec!(1000) * price)
+    }
+
+    #[test]
+    fn test_finds_triangular_cycle() {
+        let (usdc, weth, metis) = (token("USDC", 1), token("WETH", 2), token("METIS", 3));
+
+        // Implied METIS/WETH is 85/2000 = 0.0425, but the pool quotes 0.0440
+        let pairs = vec![
+            pair(&weth, &usdc, 0xa1, dec!(2000)),
+            pair(&metis, &usdc, 0xa2, dec!(85)),
+            pair(&weth, &metis, 0xa3, dec!(22.7272)),
+        ];
+
+        let found = CycleDetector::new().find_cycles(&pairs, &PairFilter::permissive(), Utc::now());
+        assert_eq!(found.len(), 1);
+        let route = &found[0].route;
+        assert_eq!(route.total_hops(), 3);
+        assert_eq!(route.pools().count(), 3);
+        assert!(found[0].profit_percentage > dec!(2));
+        // Executed against reserves, so a little below the mid-price output
+        assert!(found[0].output_amount < route.amount_out(found[0].input_amount));
+
+        // Pinning the start token re-expresses the same cycle in USDC
+        let from_usdc = CycleDetector::new()
+            .with_start_tokens(vec![usdc.address])
+            .with_input_amount(dec!(1000))
+            .find_cycles(&pairs, &PairFilter::permissive(), Utc::now());
+        assert_eq!(from_usdc.len(), 1);
+        assert_eq!(from_usdc[0].route.start_token(), &usdc);
+        assert_eq!(from_usdc[0].route.format_path(), "USDC -> METIS -> WETH -> USDC");
+
+        // Sized for maximum profit rather than for the fixed input
+        let sized = CycleDetector::new()
+            .with_start_tokens(vec![usdc.address])
+            .with_sizer(TradeSizer::default())
+            .find_cycles(&pairs, &PairFilter::permissive(), Utc::now());
+        assert!(sized[0].input_amount > dec!(1));
+        assert!(sized[0].net_profit > from_usdc[0].net_profit);
+
+        // Two hops cannot close the triangle
+        let short = CycleDetector::new().with_max_hops(2);
+        assert!(short.find_cycles(&pairs, &PairFilter::permissive(), Utc::now()).is_empty());
+    }
+
+    #[test]
+    fn test_reports_every_cycle_through_a_token() {
+        let (usdc, weth, metis) = (token("USDC", 1), token("WETH", 2), token("METIS", 3));
+        let (dai, wbtc) = (token("DAI", 4), token("WBTC", 5));
+
+        // Two disjoint mispriced triangles, both through USDC
+        let pairs = vec![
+            pair(&weth, &usdc, 0xa1, dec!(2000)),
+            pair(&metis, &usdc, 0xa2, dec!(85)),
+            pair(&weth, &metis, 0xa3, dec!(22.7272)),
+            pair(&wbtc, &usdc, 0xb1, dec!(60000)),
+            pair(&dai, &usdc, 0xb2, dec!(1)),
+            pair(&wbtc, &dai, 0xb3, dec!(61500)),
+        ];
+
+        let found = CycleDetector::new()
+            .with_start_tokens(vec![usdc.address])
+            .find_cycles(&pairs, &PairFilter::permissive(), Utc::now());
+        let mut paths: Vec<String> = found.iter().map(|o| o.route.format_path()).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["USDC -> METIS -> WETH -> USDC", "USDC -> WBTC -> DAI -> USDC"]);
+
+        // A four-hop limit still finds both simple triangles
+        let wider = CycleDetector::new()
+            .with_max_hops(4)
+            .with_start_tokens(vec![usdc.address])
+            .find_cycles(&pairs, &PairFilter::permissive(), Utc::now());
+        assert_eq!(wider.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cross_dex_spread_is_a_two_hop_cycle() {
+        let pairs = MockPriceFeed::new().get_trading_pairs().await;
+        let usdc = pairs[2].quote_token.address;
+
+        let found = CycleDetector::new()
+            .with_start_tokens(vec![usdc])
+            .with_min_profit_percent(dec!(0.5))
+            .find_cycles(&pairs, &PairFilter::default(), Utc::now());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].route.format_path(), "USDC -> METIS -> USDC");
+        assert_eq!(found[0].route.exchanges(), vec!["tethys", "netswap"]);
+        // The mock reserves imply slightly wider prices than the quoted 84/85
+        assert_eq!(found[0].profit_percentage.round_dp(3), dec!(0.698));
+    }
+}
@@ -0,0 +1,228 @@
+//! detection.rs - Cross-DEX price difference detection
+//!
+//! Compares the same pair across DEXes and reports spreads above a
+//! threshold. Pure over a pair snapshot, so recorded runs can be replayed
+//! through it offline. Pairs rejected by the PairFilter are not considered.
+//! Spreads are net of both pools' swap fees, and only buy/sell pools that
+//! form a valid ArbitrageRoute are compared. Pools are grouped by token
+//! address pair and quoted token0/token1, so a USDC/WETH listing is
+//! compared against WETH/USDC ones. Spreads can also be reported as
+//! ArbitrageOpportunity values for the cross-DEX strategy.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::filter::PairFilter;
+use crate::models::{ArbitrageOpportunity, ArbitrageRoute, PairKey, RouteError, TradingPair};
+
+/// Spread (in percent) above which a difference is reported
+pub const DEFAULT_MIN_SPREAD_PERCENT: Decimal = Decimal::from_parts(5, 0, 0, false, 1);
+
+/// The same pair priced differently on two DEXes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceDifference {
+    pub pair_id: String,
+    pub buy_exchange: String,
+    pub buy_price: Decimal,
+    pub sell_exchange: String,
+    pub sell_price: Decimal,
+    /// Spread between the quoted prices
+    pub gross_spread_percent: Decimal,
+    /// Spread left after paying the buy and sell pools' swap fees
+    pub spread_percent: Decimal,
+}
+
+/// Find price differences between the same pair on different DEXes
+///
+/// `now` is the time staleness is judged against: the wall clock for live
+/// data, the snapshot time for a replay.
+pub fn find_price_differences(
+    pairs: &[TradingPair],
+    filter: &PairFilter,
+    now: DateTime<Utc>,
+    min_spread_percent: Decimal,
+) -> Vec<PriceDifference> {
+    let hundred = Decimal::from(100);
+
+    best_spreads(pairs, filter, now)
+        .into_iter()
+        .filter(|(_, _, spread)| *spread > min_spread_percent)
+        .map(|(buy, sell, spread)| PriceDifference {
+            pair_id: buy.pair_id(),
+            buy_exchange: buy.exchange().name.clone(),
+            buy_price: buy.price,
+            sell_exchange: sell.exchange().name.clone(),
+            sell_price: sell.price,
+            gross_spread_percent: (sell.price - buy.price) / buy.price * hundred,
+            spread_percent: spread,
+        })
+        .collect()
+}
+
+/// Cross-DEX spreads above `min_spread_percent` as two-leg opportunities
+/// starting with `input_amount` of the quote token, priced at the pools'
+/// fee-adjusted quotes
+pub fn find_cross_dex_opportunities(
+    pairs: &[TradingPair],
+    filter: &PairFilter,
+    now: DateTime<Utc>,
+    min_spread_percent: Decimal,
+    input_amount: Decimal,
+) -> Vec<ArbitrageOpportunity> {
+    best_spreads(pairs, filter, now)
+        .into_iter()
+        .filter(|(_, _, spread)| *spread > min_spread_percent)
+        .filter_map(|(buy, sell, _)| {
+            let route = cross_dex_route(&buy, &sell).ok()?;
+            let output = route.amount_out(input_amount);
+            let profit = output - input_amount;
+            Some(ArbitrageOpportunity::new(route, input_amount, output, profit, profit, Decimal::ZERO))
+        })
+        .collect()
+}
+
+/// Best fee-adjusted buy/sell pools and their spread (in percent) for each
+/// token pair traded on at least two DEXes, quoted token0/token1
+fn best_spreads(
+    pairs: &[TradingPair],
+    filter: &PairFilter,
+    now: DateTime<Utc>,
+) -> Vec<(TradingPair, TradingPair, Decimal)> {
+    // Group pairs by token addresses, quoted token0/token1; ordered so
+    // results are reproducible
+    let mut pair_groups: BTreeMap<PairKey, Vec<TradingPair>> = BTreeMap::new();
+
+    for pair in pairs.iter().filter(|p| filter.check_at(p, now).is_ok()) {
+        pair_groups.entry(pair.pair_key()).or_default().push(pair.canonical());
+    }
+
+    let mut spreads = Vec::new();
+    let hundred = Decimal::from(100);
+
+    for pools in pair_groups.into_values() {
+        if pools.len() < 2 {
+            continue; // Need at least 2 DEXes for arbitrage
+        }
+
+        // Best fee-adjusted route: buy where the all-in cost is lowest and
+        // sell where the proceeds after fees are highest, in distinct pools
+        let mut best: Option<(&TradingPair, &TradingPair, Decimal)> = None;
+        for buy in pools.iter().filter(|p| p.price > Decimal::ZERO) {
+            for sell in pools.iter().filter(|p| cross_dex_route(buy, p).is_ok()) {
+                let cost = buy.buy_price();
+                let spread = (sell.sell_price() - cost) / cost * hundred;
+                if best.is_none_or(|(_, _, s)| spread > s) {
+                    best = Some((buy, sell, spread));
+                }
+            }
+        }
+
+        if let Some((buy, sell, spread)) = best {
+            spreads.push((buy.clone(), sell.clone(), spread));
+        }
+    }
+
+    spreads
+}
+
+/// Two-leg route buying base with quote in `buy`'s pool and selling it back
+/// for quote in `sell`'s
+///
+/// Fails if the pools are the same or the pairs' tokens differ by address.
+pub fn cross_dex_route(buy: &TradingPair, sell: &TradingPair) -> Result<ArbitrageRoute, RouteError> {
+    let legs = [buy.leg_from(buy.quote_token.address), sell.leg_from(sell.base_token.address)];
+    ArbitrageRoute::new(legs.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_feed::{MockPriceFeed, PriceFeed};
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn test_finds_cross_dex_spread() {
+        let pairs = MockPriceFeed::new().get_trading_pairs().await;
+
+        // WETH/USDC: 1850 vs 1852 is ~0.108% gross, less than the 0.5% in fees;
+        // METIS/USDC: 84 vs 85 is ~1.19% gross, ~0.685% after 20 + 30 bps
+        let filter = PairFilter::default();
+        let now = Utc::now();
+        let found = find_price_differences(&pairs, &filter, now, DEFAULT_MIN_SPREAD_PERCENT);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].pair_id, "METIS/USDC");
+        assert_eq!(found[0].buy_exchange, "tethys");
+        assert_eq!(found[0].sell_exchange, "netswap");
+        assert_eq!(found[0].gross_spread_percent.round_dp(2), dec!(1.19));
+        assert_eq!(found[0].spread_percent.round_dp(3), dec!(0.685));
+
+        assert_eq!(find_price_differences(&pairs, &filter, now, dec!(0.1)).len(), 1);
+        assert_eq!(find_price_differences(&pairs, &filter, now, dec!(-1)).len(), 2);
+
+        // A custom 1% fee on the Netswap METIS pool erases the edge
+        let mut custom = pairs.clone();
+        custom[2].pool.fee_bps = 100;
+        assert!(find_price_differences(&custom, &filter, now, DEFAULT_MIN_SPREAD_PERCENT).is_empty());
+
+        // Excluding the Tethys METIS pool (150k liquidity) removes its spread
+        let strict = PairFilter::default().with_min_liquidity(dec!(160000));
+        assert!(find_price_differences(&pairs, &strict, now, DEFAULT_MIN_SPREAD_PERCENT).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cross_dex_opportunities() {
+        let pairs = MockPriceFeed::new().get_trading_pairs().await;
+        let found = find_cross_dex_opportunities(
+            &pairs, &PairFilter::default(), Utc::now(), DEFAULT_MIN_SPREAD_PERCENT, dec!(1000),
+        );
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].route.format_path(), "USDC -> METIS -> USDC");
+        assert_eq!(found[0].route.exchanges(), vec!["tethys", "netswap"]);
+        // Profit on the input is the fee-adjusted spread
+        assert_eq!(found[0].input_amount, dec!(1000));
+        assert_eq!(found[0].profit_percentage.round_dp(3), dec!(0.685));
+    }
+
+    #[tokio::test]
+    async fn test_compares_inverted_listings() {
+        let pairs = MockPriceFeed::new().get_trading_pairs().await;
+        let filter = PairFilter::default();
+        let now = Utc::now();
+
+        // The Netswap METIS pool listed as USDC/METIS still pairs with Tethys
+        let mut inverted = pairs.clone();
+        let netswap = &pairs[2];
+        inverted[2] = TradingPair {
+            base_token: netswap.quote_token.clone(),
+            quote_token: netswap.base_token.clone(),
+            price: Decimal::ONE / netswap.price,
+            reserve_base: netswap.reserve_quote,
+            reserve_quote: netswap.reserve_base,
+            raw_reserves: netswap.raw_reserves.map(|(base, quote)| (quote, base)),
+            ..netswap.clone()
+        };
+        assert_eq!(inverted[2].canonical_price().round_dp(12), netswap.price);
+
+        let found = find_price_differences(&inverted, &filter, now, DEFAULT_MIN_SPREAD_PERCENT);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].pair_id, "METIS/USDC");
+        assert_eq!((found[0].buy_exchange.as_str(), found[0].sell_exchange.as_str()), ("tethys", "netswap"));
+        assert_eq!(found[0].sell_price.round_dp(12), dec!(85));
+        assert_eq!(found[0].spread_percent.round_dp(3), dec!(0.685));
+    }
+
+    #[tokio::test]
+    async fn test_cross_dex_route() {
+        let pairs = MockPriceFeed::new().get_trading_pairs().await;
+        let (netswap, tethys) = (&pairs[2], &pairs[3]);
+
+        let route = cross_dex_route(tethys, netswap).unwrap();
+        assert_eq!(route.format_path(), "USDC -> METIS -> USDC");
+        assert_eq!(route.exchanges(), vec!["tethys", "netswap"]);
+        assert!(route.amount_out(dec!(1000)) > dec!(1006));
+
+        assert_eq!(cross_dex_route(netswap, netswap).unwrap_err(), RouteError::RepeatedPool(netswap.pool.address));
+    }
+}
@@ -0,0 +1,368 @@
+//! filter.rs - Pair filtering policy shared by price feeds and detection
+//!
+//! One PairFilter decides which pools are worth tracking: minimum
+//! liquidity and 24h volume, token allow/deny lists, an exchange allowlist
+//! and a staleness limit. With a VerifiedTokens registry attached, spoofed
+//! tokens (and optionally unverified ones) are quarantined. Every exclusion
+//! carries the reason it was made.
+
+use chrono::{DateTime, Utc};
+use log::{debug, info};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::address::Address;
+use crate::models::TradingPair;
+use crate::token_list::{TokenStatus, UnverifiedTokenPolicy, VerifiedTokens};
+
+/// Minimum pool liquidity (USD) under the default policy
+pub const DEFAULT_MIN_LIQUIDITY_USD: Decimal = Decimal::from_parts(5000, 0, 0, false, 0);
+
+/// Which pairs feeds keep and detection considers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PairFilter {
+    /// Minimum `TradingPair::liquidity`, in the feed's price unit
+    pub min_liquidity: Decimal,
+    /// Minimum 24h volume in USD; pairs without market stats are kept
+    pub min_volume_h24_usd: Decimal,
+    /// If non-empty, both tokens must be listed
+    pub token_allowlist: Vec<Address>,
+    /// Pairs touching any of these tokens are excluded
+    pub token_denylist: Vec<Address>,
+    /// If non-empty, only these exchanges are kept
+    pub exchange_allowlist: Vec<String>,
+    /// Maximum age of a pair's data; pairs without a timestamp are kept
+    pub max_staleness_seconds: Option<i64>,
+    /// Treatment of tokens missing from `verified_tokens`
+    pub unverified_tokens: UnverifiedTokenPolicy,
+    /// Canonical tokens by address; token checks are skipped without one
+    #[serde(skip)]
+    pub verified_tokens: Option<Arc<VerifiedTokens>>,
+}
+
+impl Default for PairFilter {
+    fn default() -> Self {
+        PairFilter {
+            min_liquidity: DEFAULT_MIN_LIQUIDITY_USD,
+            ..Self::permissive()
+        }
+    }
+}
+
+/// Why a pair was excluded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExclusionReason {
+    LowLiquidity { liquidity: Decimal, min: Decimal },
+    LowVolume { volume_h24: Decimal, min: Decimal },
+    TokenDenied(Address),
+    TokenNotAllowed(Address),
+    ExchangeNotAllowed(String),
+    Stale { age_seconds: i64, max: i64 },
+    SpoofedToken { address: Address, symbol: String, canonical: Address },
+    UnverifiedToken(Address),
+}
+
+impl ExclusionReason {
+    /// Short label used to group exclusions in logs
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ExclusionReason::LowLiquidity { .. } => "low_liquidity",
+            ExclusionReason::LowVolume { .. } => "low_volume",
+            ExclusionReason::TokenDenied(_) => "token_denied",
+            ExclusionReason::TokenNotAllowed(_) => "token_not_allowed",
+            ExclusionReason::ExchangeNotAllowed(_) => "exchange_not_allowed",
+            ExclusionReason::Stale { .. } => "stale",
+            ExclusionReason::SpoofedToken { .. } => "spoofed_token",
+            ExclusionReason::UnverifiedToken(_) => "unverified_token",
+        }
+    }
+
+    /// Whether the pair was held back for token verification
+    pub fn is_quarantine(&self) -> bool {
+        matches!(self, ExclusionReason::SpoofedToken { .. } | ExclusionReason::UnverifiedToken(_))
+    }
+}
+
+impl fmt::Display for ExclusionReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExclusionReason::LowLiquidity { liquidity, min } => {
+                write!(f, "liquidity {} below minimum {}", liquidity, min)
+            }
+            ExclusionReason::LowVolume { volume_h24, min } => {
+                write!(f, "24h volume ${} below minimum ${}", volume_h24, min)
+            }
+            ExclusionReason::TokenDenied(token) => write!(f, "token {} is denylisted", token),
+            ExclusionReason::TokenNotAllowed(token) => write!(f, "token {} is not allowlisted", token),
+            ExclusionReason::ExchangeNotAllowed(exchange) => {
+                write!(f, "exchange {} is not allowlisted", exchange)
+            }
+            ExclusionReason::Stale { age_seconds, max } => {
+                write!(f, "data is {}s old (max {}s)", age_seconds, max)
+            }
+            ExclusionReason::SpoofedToken { address, symbol, canonical } => {
+                write!(f, "token {} spoofs {} (canonical {})", address, symbol, canonical)
+            }
+            ExclusionReason::UnverifiedToken(token) => write!(f, "token {} is not verified", token),
+        }
+    }
+}
+
+/// A pair removed by the filter
+#[derive(Debug, Clone)]
+pub struct Exclusion {
+    pub pair: TradingPair,
+    pub reason: ExclusionReason,
+}
+
+/// Pairs split by a PairFilter
+#[derive(Debug, Clone, Default)]
+pub struct FilterOutcome {
+    pub kept: Vec<TradingPair>,
+    pub excluded: Vec<Exclusion>,
+}
+
+impl FilterOutcome {
+    /// Log how many pairs `source` kept and why the rest were dropped
+    pub fn log(&self, source: &str) {
+        if self.excluded.is_empty() {
+            return;
+        }
+        let reasons: Vec<String> = self.summary().iter().map(|(k, n)| format!("{} {}", n, k)).collect();
+        info!("{}: kept {} pairs, excluded {} ({})", source, self.kept.len(), self.excluded.len(), reasons.join(", "));
+        for exclusion in &self.excluded {
+            debug!("{}: excluded {}: {}", source, exclusion.pair.full_id(), exclusion.reason);
+        }
+    }
+
+    /// Pairs held back because of spoofed or unverified tokens
+    pub fn quarantined(&self) -> impl Iterator<Item = &Exclusion> {
+        self.excluded.iter().filter(|e| e.reason.is_quarantine())
+    }
+
+    /// Number of exclusions per reason kind
+    pub fn summary(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for exclusion in &self.excluded {
+            *counts.entry(exclusion.reason.kind()).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+impl PairFilter {
+    /// A filter that keeps every pair
+    pub fn permissive() -> Self {
+        PairFilter {
+            min_liquidity: Decimal::ZERO,
+            min_volume_h24_usd: Decimal::ZERO,
+            token_allowlist: Vec::new(),
+            token_denylist: Vec::new(),
+            exchange_allowlist: Vec::new(),
+            max_staleness_seconds: None,
+            unverified_tokens: UnverifiedTokenPolicy::Allow,
+            verified_tokens: None,
+        }
+    }
+
+    /// Check tokens against a verified registry
+    pub fn with_verified_tokens(mut self, verified: VerifiedTokens) -> Self {
+        self.verified_tokens = Some(Arc::new(verified));
+        self
+    }
+
+    pub fn with_unverified_tokens(mut self, policy: UnverifiedTokenPolicy) -> Self {
+        self.unverified_tokens = policy;
+        self
+    }
+
+    pub fn with_min_liquidity(mut self, min_liquidity: Decimal) -> Self {
+        self.min_liquidity = min_liquidity;
+        self
+    }
+
+    pub fn with_min_volume_h24_usd(mut self, min_volume: Decimal) -> Self {
+        self.min_volume_h24_usd = min_volume;
+        self
+    }
+
+    pub fn with_token_allowlist(mut self, tokens: Vec<Address>) -> Self {
+        self.token_allowlist = tokens;
+        self
+    }
+
+    pub fn with_token_denylist(mut self, tokens: Vec<Address>) -> Self {
+        self.token_denylist = tokens;
+        self
+    }
+
+    pub fn with_exchange_allowlist(mut self, exchanges: Vec<String>) -> Self {
+        self.exchange_allowlist = exchanges;
+        self
+    }
+
+    pub fn with_max_staleness_seconds(mut self, seconds: i64) -> Self {
+        self.max_staleness_seconds = Some(seconds);
+        self
+    }
+
+    /// Check a pair, judging staleness against the current time
+    pub fn check(&self, pair: &TradingPair) -> Result<(), ExclusionReason> {
+        self.check_at(pair, Utc::now())
+    }
+
+    /// Check a pair, judging staleness against `now`
+    pub fn check_at(&self, pair: &TradingPair, now: DateTime<Utc>) -> Result<(), ExclusionReason> {
+        if !self.exchange_allowlist.is_empty()
+            && !self.exchange_allowlist.iter().any(|e| e.eq_ignore_ascii_case(&pair.exchange().name))
+        {
+            return Err(ExclusionReason::ExchangeNotAllowed(pair.exchange().name.clone()));
+        }
+
+        for token in [&pair.base_token, &pair.quote_token] {
+            if self.token_denylist.contains(&token.address) {
+                return Err(ExclusionReason::TokenDenied(token.address));
+            }
+            if !self.token_allowlist.is_empty() && !self.token_allowlist.contains(&token.address) {
+                return Err(ExclusionReason::TokenNotAllowed(token.address));
+            }
+        }
+
+        if let Some(verified) = &self.verified_tokens {
+            for token in [&pair.base_token, &pair.quote_token] {
+                match verified.classify(token) {
+                    TokenStatus::Verified => {}
+                    TokenStatus::Spoofed { canonical } => {
+                        return Err(ExclusionReason::SpoofedToken {
+                            address: token.address,
+                            symbol: token.symbol.clone(),
+                            canonical,
+                        });
+                    }
+                    TokenStatus::Unverified => {
+                        if self.unverified_tokens == UnverifiedTokenPolicy::Quarantine {
+                            return Err(ExclusionReason::UnverifiedToken(token.address));
+                        }
+                    }
+                }
+            }
+        }
+
+        if pair.liquidity < self.min_liquidity {
+            return Err(ExclusionReason::LowLiquidity { liquidity: pair.liquidity, min: self.min_liquidity });
+        }
+
+        if let Some(market) = &pair.market {
+            if market.volume_usd.h24 < self.min_volume_h24_usd {
+                return Err(ExclusionReason::LowVolume {
+                    volume_h24: market.volume_usd.h24,
+                    min: self.min_volume_h24_usd,
+                });
+            }
+        }
+
+        if let (Some(max), Some(updated_at)) = (self.max_staleness_seconds, pair.updated_at) {
+            let age_seconds = now.signed_duration_since(updated_at).num_seconds();
+            if age_seconds > max {
+                return Err(ExclusionReason::Stale { age_seconds, max });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Split pairs into kept and excluded, judging staleness against now
+    pub fn apply(&self, pairs: Vec<TradingPair>) -> FilterOutcome {
+        self.apply_at(pairs, Utc::now())
+    }
+
+    /// Split pairs into kept and excluded, judging staleness against `now`
+    pub fn apply_at(&self, pairs: Vec<TradingPair>, now: DateTime<Utc>) -> FilterOutcome {
+        let mut outcome = FilterOutcome::default();
+        for pair in pairs {
+            match self.check_at(&pair, now) {
+                Ok(()) => outcome.kept.push(pair),
+                Err(reason) => outcome.excluded.push(Exclusion { pair, reason }),
+            }
+        }
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{MarketStats, Token};
+    use crate::price_feed::{MockPriceFeed, PriceFeed};
+    use rust_decimal_macros::dec;
+
+    const METIS: &str = "0xDeadDeAddeAddEAddeadDEaDDEAdDeaDDeAD0000";
+
+    #[tokio::test]
+    async fn test_filter_reports_reasons() {
+        let pairs = MockPriceFeed::new().get_trading_pairs().await;
+
+        // Default policy: the 150k METIS/USDC Tethys pool is the smallest, all pass
+        assert_eq!(PairFilter::default().apply(pairs.clone()).excluded.len(), 0);
+
+        let filter = PairFilter::permissive()
+            .with_min_liquidity(dec!(300000))
+            .with_token_denylist(vec![METIS.to_lowercase().parse().unwrap()]);
+        let outcome = filter.apply(pairs.clone());
+        assert_eq!(outcome.kept.len(), 2);
+        assert_eq!(outcome.summary().get("token_denied"), Some(&2));
+
+        let filter = PairFilter::permissive().with_exchange_allowlist(vec!["Netswap".to_string()]);
+        let outcome = filter.apply(pairs.clone());
+        assert_eq!(outcome.kept.len(), 2);
+        assert_eq!(outcome.excluded[0].reason, ExclusionReason::ExchangeNotAllowed("tethys".to_string()));
+        assert_eq!(outcome.excluded[0].reason.to_string(), "exchange tethys is not allowlisted");
+    }
+
+    #[tokio::test]
+    async fn test_filter_quarantines_spoofed_tokens() {
+        let mut pairs = MockPriceFeed::new().get_trading_pairs().await;
+        // A scam "WETH" at another address, priced far off the real one
+        pairs[1].base_token = Token::new("WETH", "Wrapped Ether", 18, "0x00000000000000000000000000000000000bad02".parse().unwrap());
+        pairs[1].price = dec!(185);
+
+        let filter = PairFilter::permissive().with_verified_tokens(VerifiedTokens::metis());
+        let outcome = filter.apply(pairs.clone());
+        assert_eq!(outcome.kept.len(), 3);
+        assert_eq!(outcome.quarantined().count(), 1);
+        assert_eq!(outcome.excluded[0].reason.kind(), "spoofed_token");
+
+        // Mock USDC uses a plain "USDC" symbol at the m.USDC address, so it is
+        // verified; a token the list has never seen is only dropped on request
+        pairs[1].base_token = Token::new("HER", "Hercules", 18, "0x00000000000000000000000000000000000bad03".parse().unwrap());
+        assert_eq!(filter.apply(pairs.clone()).kept.len(), 4);
+        let strict = filter.with_unverified_tokens(UnverifiedTokenPolicy::Quarantine);
+        assert_eq!(strict.apply(pairs).summary().get("unverified_token"), Some(&1));
+    }
+
+    #[tokio::test]
+    async fn test_filter_volume_and_staleness() {
+        let mut pair = MockPriceFeed::new().get_trading_pairs().await.remove(0);
+        let now = pair.updated_at.unwrap();
+
+        let filter = PairFilter::permissive()
+            .with_min_volume_h24_usd(dec!(10000))
+            .with_max_staleness_seconds(60);
+
+        // No market stats: volume is unknown and the pair is kept
+        assert_eq!(filter.check_at(&pair, now), Ok(()));
+
+        pair.market = Some(MarketStats::default());
+        assert!(matches!(filter.check_at(&pair, now), Err(ExclusionReason::LowVolume { .. })));
+
+        pair.market.as_mut().unwrap().volume_usd.h24 = dec!(25000);
+        assert_eq!(
+            filter.check_at(&pair, now + chrono::Duration::seconds(90)),
+            Err(ExclusionReason::Stale { age_seconds: 90, max: 60 })
+        );
+    }
+}
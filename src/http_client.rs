@@ -0,0 +1,375 @@
+//! http_client.rs - Rate-limited, retrying HTTP client for REST data sources
+//!
+//! Token-bucket rate limiting per endpoint (matching DEX Screener's
+//! published per-minute limits), exponential backoff with jitter on 429/5xx,
+//! `Retry-After` handling, and per-endpoint counters so operators can see
+//! when data is degraded.
+
+use log::{debug, warn};
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// DEX Screener limit for the pairs, search and tokens endpoints
+pub const DEX_SCREENER_PAIRS_RATE_PER_MINUTE: u32 = 300;
+
+/// DEX Screener limit for token profile and boost endpoints
+pub const DEX_SCREENER_PROFILES_RATE_PER_MINUTE: u32 = 60;
+
+/// Longest `Retry-After` the client will honor before giving up
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Errors returned once all retries are exhausted (or a request is not retriable)
+#[derive(Debug, Error)]
+pub enum HttpError {
+    #[error("transport error: {0}")]
+    Transport(#[from] reqwest::Error),
+
+    #[error("HTTP status {0}")]
+    Status(u16),
+
+    #[error("{endpoint}: giving up after {attempts} attempts ({last_error})")]
+    RetriesExhausted {
+        endpoint: String,
+        attempts: u32,
+        last_error: String,
+    },
+}
+
+/// Request counters for one endpoint
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EndpointStats {
+    pub requests: u64,
+    pub successes: u64,
+    pub failures: u64,
+    pub rate_limited: u64,
+    pub retries: u64,
+    pub consecutive_failures: u64,
+    pub last_error: Option<String>,
+}
+
+impl EndpointStats {
+    /// Whether the most recent request to this endpoint failed
+    pub fn is_degraded(&self) -> bool {
+        self.consecutive_failures > 0
+    }
+}
+
+/// Backoff settings for retriable failures
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Exponential delay for `attempt` (0-based) with equal jitter
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self.base_delay.saturating_mul(2u32.saturating_pow(attempt));
+        let capped = exp.min(self.max_delay);
+        let half = capped / 2;
+        half + half.mul_f64(jitter())
+    }
+}
+
+/// Uniform random number in [0, 1) without pulling in an RNG crate
+fn jitter() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Token bucket refilled continuously at a per-minute rate
+#[derive(Debug)]
+pub struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Bucket allowing `requests_per_minute`, with bursts of up to `burst`
+    pub fn new(requests_per_minute: u32, burst: u32) -> Self {
+        let capacity = f64::from(burst.max(1));
+        TokenBucket {
+            capacity,
+            tokens: capacity,
+            refill_per_sec: f64::from(requests_per_minute.max(1)) / 60.0,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Take a token, returning how long the caller must wait first
+    fn reserve(&mut self) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.refill_per_sec)
+        }
+    }
+}
+
+/// HTTP client applying per-endpoint rate limits and retries
+#[derive(Debug, Clone)]
+pub struct RateLimitedClient {
+    client: Client,
+    default_rate: u32,
+    limits: HashMap<String, u32>,
+    buckets: Arc<Mutex<HashMap<String, Arc<Mutex<TokenBucket>>>>>,
+    stats: Arc<Mutex<HashMap<String, EndpointStats>>>,
+    retry: RetryPolicy,
+}
+
+impl RateLimitedClient {
+    /// Client limiting every endpoint to `requests_per_minute`
+    pub fn new(requests_per_minute: u32) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        RateLimitedClient {
+            client,
+            default_rate: requests_per_minute,
+            limits: HashMap::new(),
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+            stats: Arc::new(Mutex::new(HashMap::new())),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Client configured with DEX Screener's published limits
+    pub fn dex_screener() -> Self {
+        Self::new(DEX_SCREENER_PAIRS_RATE_PER_MINUTE)
+            .with_endpoint_limit("token-profiles", DEX_SCREENER_PROFILES_RATE_PER_MINUTE)
+            .with_endpoint_limit("token-boosts", DEX_SCREENER_PROFILES_RATE_PER_MINUTE)
+    }
+
+    /// Override the per-minute limit for one endpoint
+    pub fn with_endpoint_limit(mut self, endpoint: &str, requests_per_minute: u32) -> Self {
+        self.limits.insert(endpoint.to_string(), requests_per_minute);
+        self
+    }
+
+    /// Replace the retry policy
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Snapshot of per-endpoint counters
+    pub async fn stats(&self) -> HashMap<String, EndpointStats> {
+        self.stats.lock().await.clone()
+    }
+
+    /// GET `url` and decode JSON, counting against `endpoint`'s limit
+    pub async fn get_json<T: DeserializeOwned>(&self, endpoint: &str, url: &str) -> Result<T, HttpError> {
+        let mut attempt = 0;
+
+        loop {
+            self.acquire(endpoint).await;
+            self.record(endpoint, |s| s.requests += 1).await;
+            debug!("GET {} [{}] attempt {}", url, endpoint, attempt + 1);
+
+            let (error, retry_after) = match self.client.get(url).send().await {
+                Ok(response) if response.status().is_success() => match response.json::<T>().await {
+                    Ok(body) => {
+                        self.record(endpoint, |s| {
+                            s.successes += 1;
+                            s.consecutive_failures = 0;
+                        })
+                        .await;
+                        return Ok(body);
+                    }
+                    // A malformed body will not improve on retry
+                    Err(e) => return Err(self.fail(endpoint, HttpError::Transport(e)).await),
+                },
+                Ok(response) => {
+                    let status = response.status();
+                    if status == StatusCode::TOO_MANY_REQUESTS {
+                        self.record(endpoint, |s| s.rate_limited += 1).await;
+                    }
+                    if status != StatusCode::TOO_MANY_REQUESTS && !status.is_server_error() {
+                        return Err(self.fail(endpoint, HttpError::Status(status.as_u16())).await);
+                    }
+                    (HttpError::Status(status.as_u16()), retry_after(&response))
+                }
+                Err(e) => (HttpError::Transport(e), None),
+            };
+
+            if attempt >= self.retry.max_retries {
+                let exhausted = HttpError::RetriesExhausted {
+                    endpoint: endpoint.to_string(),
+                    attempts: attempt + 1,
+                    last_error: error.to_string(),
+                };
+                return Err(self.fail(endpoint, exhausted).await);
+            }
+
+            let delay = retry_after.unwrap_or_else(|| self.retry.backoff(attempt));
+            warn!("{} failed ({}), retrying in {:?}", endpoint, error, delay);
+            self.record(endpoint, |s| s.retries += 1).await;
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Wait until `endpoint`'s bucket has a token
+    async fn acquire(&self, endpoint: &str) {
+        let bucket = {
+            let mut buckets = self.buckets.lock().await;
+            buckets
+                .entry(endpoint.to_string())
+                .or_insert_with(|| {
+                    let rate = self.limits.get(endpoint).copied().unwrap_or(self.default_rate);
+                    // Allow short bursts of a tenth of the per-minute budget
+                    Arc::new(Mutex::new(TokenBucket::new(rate, rate / 10)))
+                })
+                .clone()
+        };
+
+        let wait = bucket.lock().await.reserve();
+        if !wait.is_zero() {
+            debug!("Rate limiting {} for {:?}", endpoint, wait);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    async fn record<F: FnOnce(&mut EndpointStats)>(&self, endpoint: &str, update: F) {
+        update(self.stats.lock().await.entry(endpoint.to_string()).or_default());
+    }
+
+    async fn fail(&self, endpoint: &str, error: HttpError) -> HttpError {
+        let message = error.to_string();
+        self.record(endpoint, |s| {
+            s.failures += 1;
+            s.consecutive_failures += 1;
+            s.last_error = Some(message);
+        })
+        .await;
+        error
+    }
+}
+
+/// Parse a `Retry-After` header given in seconds
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(|secs| Duration::from_secs(secs).min(MAX_RETRY_AFTER))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{MockHttpServer, MockResponse};
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn fast_retries(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        }
+    }
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+        };
+
+        for _ in 0..20 {
+            let first = policy.backoff(0);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let later = policy.backoff(10);
+            assert!(later >= Duration::from_millis(500) && later <= Duration::from_millis(1000));
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_token_bucket_spaces_requests() {
+        let mut bucket = TokenBucket::new(60, 1);
+        assert_eq!(bucket.reserve(), Duration::ZERO);
+        let wait = bucket.reserve();
+        assert!(wait > Duration::from_millis(900) && wait <= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_retries_429_honoring_retry_after() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let server = MockHttpServer::start(move |_| {
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                MockResponse::status(429).with_header("Retry-After", "0")
+            } else {
+                MockResponse::json(json!({ "pairs": [] }))
+            }
+        })
+        .await;
+
+        let client = RateLimitedClient::new(600).with_retry_policy(fast_retries(3));
+        let body: Value = client.get_json("search", &server.url).await.unwrap();
+        assert_eq!(body, json!({ "pairs": [] }));
+
+        let stats = &client.stats().await["search"];
+        assert_eq!(stats.requests, 2);
+        assert_eq!(stats.rate_limited, 1);
+        assert_eq!(stats.retries, 1);
+        assert_eq!(stats.successes, 1);
+        assert!(!stats.is_degraded());
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_and_counts_failures() {
+        let server = MockHttpServer::start(|_| MockResponse::status(503)).await;
+        let client = RateLimitedClient::new(600).with_retry_policy(fast_retries(2));
+
+        let result: Result<Value, _> = client.get_json("pairs", &server.url).await;
+        assert!(matches!(result, Err(HttpError::RetriesExhausted { attempts: 3, .. })));
+
+        // Client errors other than 429 are not retried
+        let missing = MockHttpServer::start(|_| MockResponse::status(404)).await;
+        let result: Result<Value, _> = client.get_json("tokens", &missing.url).await;
+        assert!(matches!(result, Err(HttpError::Status(404))));
+
+        let stats = client.stats().await;
+        assert_eq!(stats["pairs"].requests, 3);
+        assert!(stats["pairs"].is_degraded());
+        assert_eq!(stats["tokens"].retries, 0);
+    }
+}
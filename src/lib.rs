@@ -2,10 +2,38 @@
 //!
 //! A high-performance arbitrage scanner for Metis blockchain
 //!
-//! # Phase 1: Real Metis Price Feeds
-//! - MetisPriceFeed implementation fetching from DEX Screener
-//! - Support for Netswap and Tethys DEXes
-//! - Caching layer for price data
+//! # Price data
+//! - DexScreenerFeed (MetisPriceFeed): pairs for one chain and DEX allowlist,
+//!   discovered by search terms, watchlisted pools and token-graph crawls,
+//!   through a rate-limited, retrying client with per-endpoint stats
+//! - OnChainPriceFeed: exact reserves read from UniswapV2 pool contracts at a
+//!   single block, optionally batched through Multicall3
+//! - CompositePriceFeed: median price across feeds, withholding pools whose
+//!   sources disagree or lack quorum
+//! - PoolStream: Sync logs from eth_subscribe applied to on-chain pairs
+//!   between refreshes
+//! - RecordingPriceFeed / ReplayPriceFeed: snapshots to JSONL and back for
+//!   deterministic offline runs
+//!
+//! # Market model
+//! - Token, Exchange (router, factory, fee tier), Pool (address, ordered
+//!   tokens, fee) and TradingPair priced in quote per base
+//! - Address (20 bytes, EIP-55 checksummed) and TokenAmount (raw U256 base
+//!   units) so lookups and amounts are exact
+//! - PairKey (token0/token1 by address) compares pools listed either way
+//! - Chain registry (native and wrapped tokens, block time, Multicall3, gas
+//!   price oracle) with Metis Andromeda built in and more from config
+//! - TokenRegistry resolving ERC-20 metadata; VerifiedTokens quarantining
+//!   spoofed tokens; PairFilter deciding which pairs are scanned and why
+//!   others are not
+//!
+//! # Detection
+//! - ArbitrageStrategy implementations (cross-DEX spreads, two-pool and
+//!   triangular cycles) selected from config through StrategyRegistry
+//! - CycleDetector: hop-bounded Bellman-Ford for negative cycles over −ln of
+//!   fee-adjusted rates
+//! - Constant-product simulation with exact UniswapV2 rounding, and trade
+//!   sizing within capital limits
 //!
 //! # Architecture
 //! ```text
@@ -31,8 +59,29 @@
 //! └─────────────────┘           └─────────────────┘
 //! ```
 
+pub mod abi;
+pub mod address;
+pub mod amount;
+pub mod chain;
+pub mod config;
+pub mod cycles;
+pub mod detection;
+pub mod filter;
+pub mod http_client;
 pub mod models;
+pub mod multicall;
+pub mod pool_stream;
 pub mod price_feed;
+pub mod replay;
+pub mod rpc;
+pub mod simulation;
+pub mod sizing;
+pub mod strategy;
+pub mod token_list;
+pub mod token_registry;
+
+#[cfg(test)]
+mod test_util;
 
 // Re-export commonly used types
 pub use models::{
@@ -41,11 +90,46 @@ pub use models::{
     ArbitrageRoute,
     CachedPrice,
     Exchange,
+    MarketStats,
+    PairKey,
+    Pool,
+    PoolKind,
+    PriceKey,
+    RouteError,
     Token,
     TradingPair,
+    TxnCounts,
+    Windowed,
 };
 
-pub use price_feed::{MetisPriceFeed, MockPriceFeed, PriceFeed};
+pub use price_feed::{
+    CompositePriceFeed,
+    DexScreenerConfig,
+    DexScreenerFeed,
+    DiscoveryStrategy,
+    MetisPriceFeed,
+    MockPriceFeed,
+    OnChainPriceFeed,
+    PoolConfig,
+    PriceFeed,
+};
+pub use address::{Address, AddressError};
+pub use amount::{AmountError, TokenAmount};
+pub use chain::{Chain, ChainRegistry};
+pub use config::AppConfig;
+pub use cycles::CycleDetector;
+pub use http_client::{EndpointStats, RateLimitedClient};
+pub use detection::PriceDifference;
+pub use filter::{ExclusionReason, PairFilter};
+pub use multicall::Multicall;
+pub use pool_stream::{PoolEvent, PoolStream};
+pub use replay::{RecordingPriceFeed, ReplayPriceFeed, Snapshot};
+pub use rpc::{RpcClient, RpcError};
+pub use simulation::{simulate_route, RouteSimulation, SimulationError};
+pub use sizing::{CapitalLimits, TradeSizer};
+pub use strategy::{ArbitrageStrategy, MarketSnapshot, StrategyConfig, StrategyRegistry, StrategyReport};
+pub use token_list::{TokenStatus, UnverifiedTokenPolicy, VerifiedTokens};
+pub use token_registry::TokenRegistry;
 
 /// Version of the arbitrage engine
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -1,25 +1,68 @@
 //! main.rs - Entry point for the Furucombo Metis Arbitrage Bot
 //!
-//! Phase 1: Real Metis Price Feeds
-//! - Fetches trading pairs from Netswap and Tethys on Metis
+//! - Fetches trading pairs from DEX Screener (Netswap and Tethys on Metis by
+//!   default), merged with on-chain reserves when `onchain` is configured
 //! - Displays available pairs and their prices
-//! - Runs periodic refresh loop
-
-use furucombo_arbitrage::{MetisPriceFeed, PriceFeed, NAME, VERSION};
+//! - Runs the configured strategies on every refresh, live or replayed with
+//!   `--replay`, optionally recording snapshots with `--record`
+
+use chrono::Utc;
+use furucombo_arbitrage::filter::PairFilter;
+use furucombo_arbitrage::replay::{RecordingPriceFeed, ReplayExhausted, ReplayPriceFeed};
+use furucombo_arbitrage::chain::METIS_CHAIN_ID;
+use furucombo_arbitrage::token_list::VerifiedTokens;
+use furucombo_arbitrage::token_registry::{known_metis_tokens, DEFAULT_TOKEN_CACHE_PATH};
+use furucombo_arbitrage::config::OnChainConfig;
+use furucombo_arbitrage::{
+    AppConfig, CompositePriceFeed, DexScreenerFeed, MarketSnapshot, OnChainPriceFeed, PriceFeed, StrategyRegistry,
+    TokenRegistry, TradingPair, NAME, VERSION,
+};
 use log::{debug, error, info, warn};
-use rust_decimal::Decimal;
-use std::str::FromStr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
 /// Scan interval in seconds
 const SCAN_INTERVAL_SECONDS: u64 = 30;
 
-/// Minimum liquidity threshold in USD
-const MIN_LIQUIDITY_USD: u64 = 5000;
+/// Command-line options
+#[derive(Debug, Default)]
+struct CliArgs {
+    /// Append every refreshed snapshot to this JSONL file
+    record: Option<PathBuf>,
+    /// Replay snapshots from this JSONL file instead of fetching live data
+    replay: Option<PathBuf>,
+}
+
+impl CliArgs {
+    fn parse() -> anyhow::Result<Self> {
+        let mut args = CliArgs::default();
+        let mut iter = std::env::args().skip(1);
+
+        while let Some(arg) = iter.next() {
+            let mut path = || {
+                iter.next()
+                    .map(PathBuf::from)
+                    .ok_or_else(|| anyhow::anyhow!("{} requires a file path", arg))
+            };
+            match arg.as_str() {
+                "--record" => args.record = Some(path()?),
+                "--replay" => args.replay = Some(path()?),
+                other => anyhow::bail!("unknown argument: {} (expected --record <file> or --replay <file>)", other),
+            }
+        }
+
+        if args.record.is_some() && args.replay.is_some() {
+            anyhow::bail!("--record and --replay cannot be combined");
+        }
+        Ok(args)
+    }
+}
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let args = CliArgs::parse()?;
+
     // Initialize logging
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
         .format_timestamp_millis()
@@ -29,58 +72,98 @@ async fn main() -> anyhow::Result<()> {
     println!();
     println!("╔══════════════════════════════════════════════════════════╗");
     println!("║     🚀 Furucombo Metis Arbitrage Bot v{}              ║", VERSION);
-    println!("║     Phase 1: Real Metis Price Feeds                      ║");
+    println!("║     Multi-DEX Arbitrage Detection                        ║");
     println!("╚══════════════════════════════════════════════════════════╝");
     println!();
 
     info!("Starting {} v{}", NAME, VERSION);
-    info!("Phase 1: Real Metis Price Feeds (Netswap + Tethys)");
-
-    // Initialize the price feed
-    // Phase 1: Using MetisPriceFeed (real data from DEX Screener)
-    let price_feed: Arc<dyn PriceFeed + Send + Sync> = Arc::new(MetisPriceFeed::new());
-
-    info!("✓ Price feed initialized: MetisPriceFeed");
-    info!("  - DEX Screener API: https://api.dexscreener.com/latest/dex");
-    info!("  - Supported DEXes: Netswap, Tethys");
-    info!("  - Chain: Metis (Chain ID: 1088)");
+    info!("Cross-DEX and multi-hop arbitrage detection");
+
+    // Chain, DEXes, discovery and filters come from FURUCOMBO_CONFIG (Metis by default)
+    let config = AppConfig::from_env()?;
+    let filter = config.filter.clone().with_verified_tokens(load_verified_tokens(&config)?);
+
+    let (price_feed, screener_feed, replay_feed) = match &args.replay {
+        Some(path) => {
+            // Replay: recorded snapshots, no network access
+            let replay = Arc::new(ReplayPriceFeed::open(path)?);
+            info!("✓ Price feed initialized: ReplayPriceFeed ({})", path.display());
+            (replay.clone() as Arc<dyn PriceFeed + Send + Sync>, None, Some(replay))
+        }
+        None => {
+            let tokens = build_token_registry(&config).await?;
+            let screener_feed = Arc::new(build_screener_feed(&config, &filter, tokens.clone()));
+            let live_feed: Arc<dyn PriceFeed + Send + Sync> = match &config.onchain {
+                Some(onchain) => build_composite_feed(&config, onchain, &filter, screener_feed.clone(), tokens),
+                None => screener_feed.clone(),
+            };
+            let price_feed: Arc<dyn PriceFeed + Send + Sync> = match &args.record {
+                Some(path) => {
+                    info!("  - Recording snapshots to {}", path.display());
+                    Arc::new(RecordingPriceFeed::new(live_feed, path))
+                }
+                None => live_feed,
+            };
+            (price_feed, Some(screener_feed), None)
+        }
+    };
     println!();
 
-    // Initial data fetch
+    // Initial data fetch (a replay loads its first snapshot here)
     info!("📊 Fetching initial market data...");
-    match price_feed.refresh().await {
+    let initial_fetch = price_feed.refresh().await;
+    match &initial_fetch {
         Ok(_) => info!("✓ Initial data fetch successful"),
         Err(e) => {
             error!("✗ Initial data fetch failed: {}", e);
             warn!("Will retry in the main loop...");
         }
     }
+    // A replay scans the snapshot already loaded before advancing
+    let mut preloaded = args.replay.is_some() && initial_fetch.is_ok();
 
     // Display initial pairs
-    display_trading_pairs(&price_feed).await;
+    display_trading_pairs(&price_feed, &filter).await;
 
     // Main scanning loop
     info!("🔄 Starting scan loop (interval: {}s)...", SCAN_INTERVAL_SECONDS);
     println!();
 
+    let strategies = config.strategy_registry();
+    info!("🧠 Strategies: {}", strategies.names().join(", "));
     let mut interval = tokio::time::interval(Duration::from_secs(SCAN_INTERVAL_SECONDS));
     let mut scan_count: u64 = 0;
 
     loop {
-        interval.tick().await;
+        // Replays run back to back instead of waiting for the scan interval
+        if args.replay.is_none() {
+            interval.tick().await;
+        }
         scan_count += 1;
 
         debug!("─────────────────────────────────────────────────────────────");
         info!("📡 Scan #{}: Refreshing price data...", scan_count);
 
         // Refresh price data
-        match price_feed.refresh().await {
+        let refreshed = if std::mem::take(&mut preloaded) { Ok(()) } else { price_feed.refresh().await };
+        match refreshed {
             Ok(_) => {
                 let pairs = price_feed.get_trading_pairs().await;
                 info!("✓ Scan #{} complete: {} pairs available", scan_count, pairs.len());
 
-                // Find potential arbitrage opportunities (Phase 1: just detect price differences)
-                find_price_differences(&pairs).await;
+                // Staleness is judged at recording time when replaying
+                let now = match &replay_feed {
+                    Some(replay) => replay.current_timestamp().await.unwrap_or_else(Utc::now),
+                    None => Utc::now(),
+                };
+
+                // Find potential arbitrage opportunities with every configured strategy
+                warn_symbol_collisions(&pairs);
+                report_opportunities(&strategies, &MarketSnapshot::new(&pairs, &filter, now));
+            }
+            Err(e) if e.is::<ReplayExhausted>() => {
+                info!("⏹ Replay finished: {}", e);
+                return Ok(());
             }
             Err(e) => {
                 error!("✗ Scan #{} failed: {}", scan_count, e);
@@ -88,14 +171,132 @@ async fn main() -> anyhow::Result<()> {
         }
 
         // Stats every 10 scans
-        if scan_count % 10 == 0 {
+        if scan_count.is_multiple_of(10) {
             info!("📈 Stats: {} scans completed", scan_count);
+            let endpoint_stats = match &screener_feed {
+                Some(feed) => feed.endpoint_stats().await,
+                None => Default::default(),
+            };
+            for (endpoint, stats) in endpoint_stats {
+                let line = format!(
+                    "  - {}: {} requests, {} failures, {} rate limited, {} retries",
+                    endpoint, stats.requests, stats.failures, stats.rate_limited, stats.retries
+                );
+                if stats.is_degraded() {
+                    warn!("{} (degraded: {})", line, stats.last_error.unwrap_or_default());
+                } else {
+                    info!("{}", line);
+                }
+            }
         }
     }
 }
 
+/// Canonical tokens: the built-in Metis set (on Metis) plus the configured
+/// token list
+fn load_verified_tokens(config: &AppConfig) -> anyhow::Result<VerifiedTokens> {
+    let Some(source) = &config.token_list else {
+        let chain_id = config.dex_screener.evm_chain_id;
+        return Ok(if chain_id == METIS_CHAIN_ID {
+            VerifiedTokens::metis()
+        } else {
+            VerifiedTokens::new(chain_id)
+        });
+    };
+
+    let base = if source.chain_id == METIS_CHAIN_ID {
+        VerifiedTokens::metis()
+    } else {
+        VerifiedTokens::new(source.chain_id)
+    };
+    let verified = source.aliases.iter().fold(base.with_token_list_file(&source.path)?, |verified, (alias, symbol)| {
+        verified.with_alias(alias, symbol)
+    });
+    info!("✓ Verified tokens: {} from {}", verified.len(), source.path.display());
+    Ok(verified)
+}
+
+/// Token metadata (decimals, symbols) shared by the live feeds and
+/// persisted across runs
+async fn build_token_registry(config: &AppConfig) -> anyhow::Result<TokenRegistry> {
+    let screener = &config.dex_screener;
+    let tokens = TokenRegistry::with_cache_file(&screener.rpc_url, DEFAULT_TOKEN_CACHE_PATH)?
+        .with_chain_id(screener.evm_chain_id);
+    if screener.evm_chain_id == METIS_CHAIN_ID {
+        tokens.seed(known_metis_tokens()).await;
+    }
+    info!("✓ Token registry ready: {} known tokens", tokens.len().await);
+    Ok(tokens)
+}
+
+/// Build the live DEX Screener feed from the loaded configuration
+fn build_screener_feed(config: &AppConfig, filter: &PairFilter, tokens: TokenRegistry) -> DexScreenerFeed {
+    let screener = config.screener_config();
+    let feed = DexScreenerFeed::with_config(screener.clone())
+        .with_token_registry(tokens)
+        .with_filter(filter.clone());
+
+    info!("✓ Price feed initialized: DexScreenerFeed");
+    info!("  - DEX Screener API: {}", screener.base_url);
+    if screener.dex_allowlist.is_empty() {
+        info!("  - Supported DEXes: all");
+    } else {
+        info!("  - Supported DEXes: {}", screener.dex_allowlist.join(", "));
+    }
+    match config.chain() {
+        Some(chain) => info!(
+            "  - Chain: {} (id {}, DEX Screener id: {}, gas in {}, {}ms blocks)",
+            chain.name, chain.id, screener.chain_id, chain.native_currency.symbol, chain.block_time_ms
+        ),
+        None => warn!(
+            "  - Chain {} is not in the chain registry (DEX Screener id: {})",
+            screener.evm_chain_id, screener.chain_id
+        ),
+    }
+    feed
+}
+
+/// Read the configured pools on-chain and merge them with DEX Screener,
+/// scanning only pools whose sources agree
+fn build_composite_feed(
+    config: &AppConfig,
+    onchain: &OnChainConfig,
+    filter: &PairFilter,
+    screener_feed: Arc<DexScreenerFeed>,
+    tokens: TokenRegistry,
+) -> Arc<dyn PriceFeed + Send + Sync> {
+    let rpc_url = onchain.rpc_url.as_deref().unwrap_or(&config.dex_screener.rpc_url);
+    let mut onchain_feed = OnChainPriceFeed::new(rpc_url, onchain.pools.clone())
+        .with_token_registry(tokens)
+        // Liquidity is in quote units on-chain, so only token checks apply
+        .with_filter(PairFilter {
+            verified_tokens: filter.verified_tokens.clone(),
+            unverified_tokens: filter.unverified_tokens,
+            ..PairFilter::permissive()
+        });
+    onchain_feed = match config.chain() {
+        Some(chain) => onchain_feed.with_chain(&chain),
+        None => onchain_feed.with_chain_id(config.dex_screener.evm_chain_id),
+    };
+    if !config.dex_screener.exchanges.is_empty() {
+        onchain_feed = onchain_feed.with_exchanges(config.dex_screener.exchanges.clone());
+    }
+
+    info!("✓ Price feed initialized: OnChainPriceFeed ({} pools via {})", onchain.pools.len(), rpc_url);
+    info!("  - CompositePriceFeed: quorum {}, tolerance {} bps", onchain.quorum, onchain.tolerance_bps);
+    Arc::new(
+        CompositePriceFeed::new(vec![
+            ("dexscreener".to_string(), screener_feed as Arc<dyn PriceFeed>),
+            ("onchain".to_string(), Arc::new(onchain_feed) as Arc<dyn PriceFeed>),
+        ])
+        .with_quote_liquidity_source("onchain")
+        .with_quorum(onchain.quorum)
+        .with_tolerance_bps(onchain.tolerance_bps),
+    )
+}
+
 /// Display all available trading pairs
-async fn display_trading_pairs(price_feed: &Arc<dyn PriceFeed + Send + Sync>) {
+async fn display_trading_pairs(price_feed: &Arc<dyn PriceFeed + Send + Sync>, filter: &PairFilter) {
     let pairs = price_feed.get_trading_pairs().await;
 
     if pairs.is_empty() {
@@ -110,15 +311,14 @@ async fn display_trading_pairs(price_feed: &Arc<dyn PriceFeed + Send + Sync>) {
     println!("│ Pair             │ Exchange     │ Price (USD)    │ Liquidity (USD)             │");
     println!("├──────────────────┼──────────────┼────────────────┼─────────────────────────────┤");
 
-    let min_liquidity = Decimal::from(MIN_LIQUIDITY_USD);
     let mut displayed = 0;
 
-    for pair in pairs.iter().filter(|p| p.liquidity >= min_liquidity) {
+    for pair in pairs.iter().filter(|p| filter.check(p).is_ok()) {
         println!(
-            "│ {:16} │ {:12} │ {:>14.4} │ {:>27.2} │",
+            "│ {:16} │ {:12} │ {:>14} │ {:>27.2} │",
             format!("{}/{}", pair.base_token.symbol, pair.quote_token.symbol),
-            pair.exchange.name,
-            pair.price,
+            pair.exchange().name,
+            pair.price_usd.map(|p| format!("{:.4}", p)).unwrap_or_else(|| "-".to_string()),
             pair.liquidity
         );
         displayed += 1;
@@ -132,63 +332,48 @@ async fn display_trading_pairs(price_feed: &Arc<dyn PriceFeed + Send + Sync>) {
 
     println!("└──────────────────┴──────────────┴────────────────┴─────────────────────────────┘");
     println!();
-    info!("Total pairs: {} (showing {} with liquidity >= ${})",
-        pairs.len(), displayed, MIN_LIQUIDITY_USD);
+    info!("Total pairs: {} (showing {} passing filters, liquidity >= ${})",
+        pairs.len(), displayed, filter.min_liquidity);
 }
 
-/// Find price differences between the same pair on different DEXes
-/// This is a simplified Phase 1 implementation - just detection, no execution
-async fn find_price_differences(pairs: &[furucombo_arbitrage::TradingPair]) {
-    use std::collections::HashMap;
-
-    // Group pairs by token pair (base/quote)
-    let mut pair_groups: HashMap<String, Vec<&furucombo_arbitrage::TradingPair>> = HashMap::new();
-
-    for pair in pairs {
-        let key = format!("{}/{}", pair.base_token.symbol, pair.quote_token.symbol);
-        pair_groups.entry(key).or_default().push(pair);
+/// Warn about symbols shared by several token addresses
+fn warn_symbol_collisions(pairs: &[TradingPair]) {
+    for collision in VerifiedTokens::symbol_collisions(pairs) {
+        warn!(
+            "⚠ Symbol {} is used by {} tokens: {}",
+            collision.symbol,
+            collision.addresses.len(),
+            collision.addresses.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ")
+        );
     }
+}
 
-    // Find pairs listed on multiple DEXes
-    let mut opportunities_found = 0;
-
-    for (pair_id, exchanges) in pair_groups.iter() {
-        if exchanges.len() < 2 {
-            continue; // Need at least 2 DEXes for arbitrage
+/// Log the opportunities each strategy finds in the snapshot
+fn report_opportunities(strategies: &StrategyRegistry, market: &MarketSnapshot<'_>) {
+    for report in strategies.run(market) {
+        for opportunity in &report.opportunities {
+            let route = &opportunity.route;
+            info!(
+                "💡 [{}] {} | {:.2}% after fees via {}",
+                report.strategy, route.format_path(), opportunity.profit_percentage, route.exchanges().join(", ")
+            );
+            info!(
+                "   Trade {} {} → {} profit",
+                opportunity.input_amount, route.start_token().symbol, opportunity.net_profit
+            );
         }
 
-        // Find min and max prices
-        let prices: Vec<(Decimal, &str)> = exchanges
-            .iter()
-            .map(|p| (p.price, p.exchange.name.as_str()))
-            .collect();
-
-        let (min_price, min_exchange) = prices.iter().min_by(|a, b| a.0.cmp(&b.0)).unwrap();
-        let (max_price, max_exchange) = prices.iter().max_by(|a, b| a.0.cmp(&b.0)).unwrap();
-
-        // Calculate spread
-        if *min_price > Decimal::ZERO {
-            let spread = ((*max_price - *min_price) / *min_price) * Decimal::from(100);
-
-            // Only report significant spreads (> 0.5%)
-            if spread > Decimal::from_str("0.5").unwrap() {
-                opportunities_found += 1;
-
-                info!(
-                    "💡 Potential opportunity: {} | Spread: {:.2}%",
-                    pair_id, spread
-                );
-                info!(
-                    "   Buy on {} @ ${:.4} → Sell on {} @ ${:.4}",
-                    min_exchange, min_price, max_exchange, max_price
-                );
-            }
+        let diagnostics = &report.diagnostics;
+        if report.opportunities.is_empty() {
+            debug!(
+                "[{}] No opportunities among {} pairs ({:?})",
+                report.strategy, diagnostics.pairs_considered, diagnostics.elapsed
+            );
+        } else {
+            info!(
+                "🎯 [{}] {} opportunities among {} pairs ({:?})",
+                report.strategy, report.opportunities.len(), diagnostics.pairs_considered, diagnostics.elapsed
+            );
         }
     }
-
-    if opportunities_found == 0 {
-        debug!("No significant price differences detected this scan");
-    } else {
-        info!("🎯 {} potential opportunities detected", opportunities_found);
-    }
 }
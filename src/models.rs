@@ -1,10 +1,27 @@
 //! models.rs - Core data structures for the arbitrage engine
 //!
-//! Phase 1: Defines Token, Exchange, TradingPair and related types
+//! Defines Token, Exchange, Pool, TradingPair and the route and opportunity
+//! types detection produces. An Exchange carries its router, factory and
+//! default fee tier; a Pool its own address, ordered tokens and any fee
+//! override, which quoted prices and leg outputs are net of. Tokens and
+//! exchanges refer to their Chain by id.
+//!
+//! A TradingPair's price is quote per base. PairKey names a pair by its
+//! sorted token addresses, as on-chain, so pairs listed either way round
+//! can be re-quoted token0/token1 and compared. Reserves and simulated swap
+//! amounts are kept as raw TokenAmounts, exact to the last base unit.
+//! ArbitrageRoute can only be built from legs that chain token to token,
+//! return to the starting token and touch each pool once.
 
+use primitive_types::U256;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use thiserror::Error;
+
+use crate::address::Address;
+use crate::amount::{AmountError, TokenAmount};
+use crate::chain::METIS_CHAIN_ID;
 
 /// Represents a token on the blockchain
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -12,18 +29,42 @@ pub struct Token {
     pub symbol: String,
     pub name: String,
     pub decimals: u8,
-    pub address: String,
+    pub address: Address,
+    /// Chain the token contract lives on
+    #[serde(default = "default_chain_id")]
+    pub chain_id: u64,
+}
+
+fn default_chain_id() -> u64 {
+    METIS_CHAIN_ID
 }
 
 impl Token {
-    pub fn new(symbol: &str, name: &str, decimals: u8, address: &str) -> Self {
+    /// A token on Metis Andromeda; see `with_chain_id` for other chains
+    pub fn new(symbol: &str, name: &str, decimals: u8, address: Address) -> Self {
         Token {
             symbol: symbol.to_string(),
             name: name.to_string(),
             decimals,
-            address: address.to_string(),
+            address,
+            chain_id: METIS_CHAIN_ID,
         }
     }
+
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    /// An amount of this token in base units
+    pub fn amount(&self, raw: U256) -> TokenAmount {
+        TokenAmount::new(raw, self.decimals)
+    }
+
+    /// An amount of this token from a human value, e.g. 1.5 USDC
+    pub fn parse_amount(&self, value: Decimal) -> Result<TokenAmount, AmountError> {
+        TokenAmount::from_decimal(value, self.decimals)
+    }
 }
 
 impl fmt::Display for Token {
@@ -32,27 +73,171 @@ impl fmt::Display for Token {
     }
 }
 
+/// Netswap router on Metis Andromeda
+pub const NETSWAP_ROUTER: &str = "0x1E876cCe41B7b844FDe09E38Fa1cf00f213bFf56";
+
+/// Tethys router on Metis Andromeda
+pub const TETHYS_ROUTER: &str = "0x81b9FA50D5f5155Ee17817C21702C3AE4780AD09";
+
+/// Swap fee of a standard UniswapV2 pool, in basis points
+pub const DEFAULT_POOL_FEE_BPS: u32 = 30;
+
+/// Netswap swap fee, in basis points
+pub const NETSWAP_FEE_BPS: u32 = 30;
+
+/// Tethys swap fee, in basis points
+pub const TETHYS_FEE_BPS: u32 = 20;
+
+/// Basis points in a whole
+const BPS_DENOMINATOR: u32 = 10_000;
+
+fn default_fee_bps() -> u32 {
+    DEFAULT_POOL_FEE_BPS
+}
+
 /// Represents a decentralized exchange
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct Exchange {
     pub name: String,
-    pub chain: String,
-    pub router_address: String,
+    /// Chain the exchange is deployed on
+    #[serde(default = "default_chain_id")]
+    pub chain_id: u64,
+    /// Router that swaps are sent through (None if not known)
+    #[serde(default)]
+    pub router_address: Option<Address>,
+    /// Factory that deploys the exchange's pools (None if not known)
+    #[serde(default)]
+    pub factory_address: Option<Address>,
+    /// Swap fee of the exchange's pools unless a pool overrides it
+    #[serde(default = "default_fee_bps")]
+    pub fee_bps: u32,
 }
 
 impl Exchange {
-    pub fn new(name: &str, chain: &str, router_address: &str) -> Self {
+    pub fn new(name: &str, chain_id: u64) -> Self {
         Exchange {
             name: name.to_string(),
-            chain: chain.to_string(),
-            router_address: router_address.to_string(),
+            chain_id,
+            router_address: None,
+            factory_address: None,
+            fee_bps: DEFAULT_POOL_FEE_BPS,
         }
     }
+
+    pub fn with_fee_bps(mut self, fee_bps: u32) -> Self {
+        self.fee_bps = fee_bps;
+        self
+    }
+
+    pub fn with_router(mut self, router_address: Address) -> Self {
+        self.router_address = Some(router_address);
+        self
+    }
+
+    pub fn with_factory(mut self, factory_address: Address) -> Self {
+        self.factory_address = Some(factory_address);
+        self
+    }
+
+    /// The entry of `known` named `name`, or a bare exchange without router
+    /// and factory
+    pub fn from_known(known: &[Exchange], name: &str, chain_id: u64) -> Exchange {
+        known.iter()
+            .find(|e| e.name.eq_ignore_ascii_case(name))
+            .cloned()
+            .unwrap_or_else(|| Exchange::new(name, chain_id))
+    }
+}
+
+/// Netswap and Tethys with their routers and fee tiers
+pub fn known_metis_exchanges() -> Vec<Exchange> {
+    let router = |address: &str| address.parse().expect("known router address is checksummed");
+    vec![
+        Exchange::new("netswap", METIS_CHAIN_ID).with_router(router(NETSWAP_ROUTER)).with_fee_bps(NETSWAP_FEE_BPS),
+        Exchange::new("tethys", METIS_CHAIN_ID).with_router(router(TETHYS_ROUTER)).with_fee_bps(TETHYS_FEE_BPS),
+    ]
 }
 
 impl fmt::Display for Exchange {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} ({})", self.name, self.chain)
+        write!(f, "{} (chain {})", self.name, self.chain_id)
+    }
+}
+
+/// Pricing curve a pool follows
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolKind {
+    /// UniswapV2-style x * y = k
+    #[default]
+    ConstantProduct,
+    /// Solidly-style stable curve
+    Stable,
+    /// UniswapV3-style concentrated liquidity
+    Concentrated,
+}
+
+/// A liquidity pool on an exchange
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Pool {
+    pub address: Address,
+    pub exchange: Exchange,
+    /// The token with the lower address
+    pub token0: Token,
+    pub token1: Token,
+    pub fee_bps: u32,
+    pub kind: PoolKind,
+}
+
+impl Pool {
+    /// Create a constant-product pool charging the exchange's fee; tokens
+    /// are ordered by address
+    pub fn new(address: Address, exchange: Exchange, token_a: Token, token_b: Token) -> Self {
+        let (token0, token1) = if token_a.address <= token_b.address {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        };
+
+        Pool {
+            address,
+            fee_bps: exchange.fee_bps,
+            exchange,
+            token0,
+            token1,
+            kind: PoolKind::default(),
+        }
+    }
+
+    pub fn with_fee_bps(mut self, fee_bps: u32) -> Self {
+        self.fee_bps = fee_bps;
+        self
+    }
+
+    pub fn with_kind(mut self, kind: PoolKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Swap fee as a fraction (30 bps = 0.003)
+    pub fn fee_rate(&self) -> Decimal {
+        Decimal::from(self.fee_bps) / Decimal::from(BPS_DENOMINATOR)
+    }
+
+    /// Part of an input amount left to trade once the fee is taken
+    pub fn amount_after_fee(&self, amount_in: Decimal) -> Decimal {
+        amount_in * Decimal::from(BPS_DENOMINATOR.saturating_sub(self.fee_bps)) / Decimal::from(BPS_DENOMINATOR)
+    }
+
+    /// Whether `address` is one of the pool's tokens
+    pub fn contains(&self, address: Address) -> bool {
+        self.token0.address == address || self.token1.address == address
+    }
+}
+
+impl fmt::Display for Pool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{} {} ({})", self.token0, self.token1, self.exchange.name, self.address)
     }
 }
 
@@ -61,18 +246,34 @@ impl fmt::Display for Exchange {
 pub struct TradingPair {
     pub base_token: Token,
     pub quote_token: Token,
-    pub exchange: Exchange,
+    pub pool: Pool,
+    /// Quote tokens per base token
     pub price: Decimal,
+    /// USD price of the base token, where the source reports one
+    #[serde(default)]
+    pub price_usd: Option<Decimal>,
     pub liquidity: Decimal,
     pub reserve_base: Decimal,
     pub reserve_quote: Decimal,
+    /// Block number the reserves were read at (None for off-chain sources)
+    #[serde(default)]
+    pub reserve_block: Option<u64>,
+    /// Exact base and quote reserves in base units (None for off-chain sources)
+    #[serde(default)]
+    pub raw_reserves: Option<(TokenAmount, TokenAmount)>,
+    /// Volume, flow and age reported by market data sources (None on-chain)
+    #[serde(default)]
+    pub market: Option<MarketStats>,
+    /// When this pair's price and reserves were last observed
+    #[serde(default)]
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl TradingPair {
     pub fn new(
         base_token: Token,
         quote_token: Token,
-        exchange: Exchange,
+        pool: Pool,
         price: Decimal,
         liquidity: Decimal,
         reserve_base: Decimal,
@@ -81,11 +282,121 @@ impl TradingPair {
         TradingPair {
             base_token,
             quote_token,
-            exchange,
+            pool,
             price,
+            price_usd: None,
             liquidity,
             reserve_base,
             reserve_quote,
+            reserve_block: None,
+            raw_reserves: None,
+            market: None,
+            updated_at: Some(chrono::Utc::now()),
+        }
+    }
+
+    /// Attach the base token's USD price
+    pub fn with_price_usd(mut self, price_usd: Decimal) -> Self {
+        self.price_usd = Some(price_usd);
+        self
+    }
+
+    /// Attach the block number the reserves were read at
+    pub fn with_reserve_block(mut self, block: u64) -> Self {
+        self.reserve_block = Some(block);
+        self
+    }
+
+    /// Attach the exact base and quote reserves
+    pub fn with_raw_reserves(mut self, base: TokenAmount, quote: TokenAmount) -> Self {
+        self.raw_reserves = Some((base, quote));
+        self
+    }
+
+    /// Attach market statistics (volume, transactions, price change, age)
+    pub fn with_market_stats(mut self, market: MarketStats) -> Self {
+        self.market = Some(market);
+        self
+    }
+
+    /// Exchange the pair's pool belongs to
+    pub fn exchange(&self) -> &Exchange {
+        &self.pool.exchange
+    }
+
+    /// Quote paid per base bought through the pool, fee included
+    pub fn buy_price(&self) -> Decimal {
+        self.price
+            .checked_div(Decimal::ONE - self.pool.fee_rate())
+            .unwrap_or(Decimal::MAX)
+    }
+
+    /// Quote received per base sold into the pool, fee deducted
+    pub fn sell_price(&self) -> Decimal {
+        self.pool.amount_after_fee(self.price)
+    }
+
+    /// Leg swapping `from` for the pair's other token through its pool, or
+    /// None if `from` is neither token
+    pub fn leg_from(&self, from: Address) -> Option<ArbitrageLeg> {
+        let reserves = self.token_reserves();
+        let (from, to, price, reserves) = if from == self.base_token.address {
+            (&self.base_token, &self.quote_token, self.price, reserves)
+        } else if from == self.quote_token.address {
+            (&self.quote_token, &self.base_token, invert_price(self.price), reserves.map(|(base, quote)| (quote, base)))
+        } else {
+            return None;
+        };
+
+        let leg = ArbitrageLeg::new(from.clone(), to.clone(), self.pool.clone(), price, self.liquidity);
+        Some(match reserves {
+            Some((reserve_in, reserve_out)) => leg.with_reserves(reserve_in, reserve_out),
+            None => leg,
+        })
+    }
+
+    /// Base and quote reserves in base units: exact when read on-chain,
+    /// otherwise the reported reserves rounded down
+    pub fn token_reserves(&self) -> Option<(TokenAmount, TokenAmount)> {
+        if let Some(raw) = self.raw_reserves {
+            return Some(raw);
+        }
+        let base = TokenAmount::from_decimal_floor(self.reserve_base, self.base_token.decimals).ok()?;
+        let quote = TokenAmount::from_decimal_floor(self.reserve_quote, self.quote_token.decimals).ok()?;
+        Some((base, quote))
+    }
+
+    /// Orientation-independent key of the pair's tokens
+    pub fn pair_key(&self) -> PairKey {
+        PairKey::new(self.base_token.address, self.quote_token.address)
+    }
+
+    /// Whether the base token is token0, i.e. the pair is quoted as on-chain
+    pub fn is_canonical(&self) -> bool {
+        self.base_token.address <= self.quote_token.address
+    }
+
+    /// Price of token0 in token1, whichever way round the pair is quoted
+    pub fn canonical_price(&self) -> Decimal {
+        if self.is_canonical() { self.price } else { invert_price(self.price) }
+    }
+
+    /// The pair quoted token0/token1: base and quote (with their reserves)
+    /// swapped and the price inverted if needed; the USD price follows the
+    /// new base token
+    pub fn canonical(&self) -> TradingPair {
+        if self.is_canonical() {
+            return self.clone();
+        }
+        TradingPair {
+            base_token: self.quote_token.clone(),
+            quote_token: self.base_token.clone(),
+            price: invert_price(self.price),
+            price_usd: self.price_usd.and_then(|usd| usd.checked_div(self.price)),
+            reserve_base: self.reserve_quote,
+            reserve_quote: self.reserve_base,
+            raw_reserves: self.raw_reserves.map(|(base, quote)| (quote, base)),
+            ..self.clone()
         }
     }
 
@@ -97,7 +408,7 @@ impl TradingPair {
     /// Returns full identifier including exchange
     pub fn full_id(&self) -> String {
         format!("{}:{}/{}",
-            self.exchange.name,
+            self.pool.exchange.name,
             self.base_token.symbol,
             self.quote_token.symbol
         )
@@ -109,16 +420,142 @@ impl fmt::Display for TradingPair {
         write!(f, "{}/{} on {} @ {}",
             self.base_token.symbol,
             self.quote_token.symbol,
-            self.exchange.name,
+            self.pool.exchange.name,
             self.price
         )
     }
 }
 
+/// Orientation-independent identity of a token pair
+///
+/// Tokens are ordered by address as in UniswapV2 pools (token0 < token1),
+/// so WETH/USDC and USDC/WETH listings of the same tokens share a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PairKey {
+    pub token0: Address,
+    pub token1: Address,
+}
+
+impl PairKey {
+    pub fn new(a: Address, b: Address) -> Self {
+        let (token0, token1) = if a <= b { (a, b) } else { (b, a) };
+        PairKey { token0, token1 }
+    }
+
+    /// Convert a canonical price (token1 per token0) into quote per `base`,
+    /// or None if `base` is not in the pair
+    pub fn orient(&self, canonical_price: Decimal, base: Address) -> Option<Decimal> {
+        if base == self.token0 {
+            Some(canonical_price)
+        } else if base == self.token1 {
+            Some(invert_price(canonical_price))
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for PairKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.token0, self.token1)
+    }
+}
+
+/// 1 / price, or zero for a zero price
+fn invert_price(price: Decimal) -> Decimal {
+    Decimal::ONE.checked_div(price).unwrap_or(Decimal::ZERO)
+}
+
+/// Cache key identifying a single pool's quote
+///
+/// Keyed by exchange, pool and canonical token pair so pools for the same
+/// symbols on different DEXes, or spoofed tokens reusing a symbol, never
+/// overwrite each other, whichever way round a source lists the tokens.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PriceKey {
+    pub exchange: String,
+    pub pool_address: Address,
+    pub pair: PairKey,
+}
+
+impl PriceKey {
+    pub fn new(exchange: &str, pool_address: Address, pair: PairKey) -> Self {
+        PriceKey {
+            exchange: exchange.to_string(),
+            pool_address,
+            pair,
+        }
+    }
+
+    /// Key for the pool a trading pair was quoted from
+    pub fn from_pair(pair: &TradingPair) -> Self {
+        PriceKey::new(&pair.pool.exchange.name, pair.pool.address, pair.pair_key())
+    }
+}
+
+/// A value reported over DEX Screener's rolling windows
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Windowed<T> {
+    pub m5: T,
+    pub h1: T,
+    pub h6: T,
+    pub h24: T,
+}
+
+/// Buy and sell transaction counts over one window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxnCounts {
+    pub buys: u64,
+    pub sells: u64,
+}
+
+impl TxnCounts {
+    pub fn total(&self) -> u64 {
+        self.buys + self.sells
+    }
+
+    /// Share of transactions that were buys (None without any transactions)
+    pub fn buy_ratio(&self) -> Option<Decimal> {
+        match self.total() {
+            0 => None,
+            total => Some(Decimal::from(self.buys) / Decimal::from(total)),
+        }
+    }
+}
+
+/// Market activity for a pool, as reported by DEX Screener
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarketStats {
+    /// Traded volume in USD
+    pub volume_usd: Windowed<Decimal>,
+    pub txns: Windowed<TxnCounts>,
+    /// Price change in percent
+    pub price_change_percent: Windowed<Decimal>,
+    /// Fully diluted valuation of the base token in USD
+    pub fdv_usd: Option<Decimal>,
+    pub pair_created_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl MarketStats {
+    /// Time since the pool was created (None if unknown)
+    pub fn age(&self, now: chrono::DateTime<chrono::Utc>) -> Option<chrono::Duration> {
+        self.pair_created_at.map(|created| now.signed_duration_since(created))
+    }
+
+    /// Whether 24h flow is lopsided: the buy share is outside
+    /// `[min_ratio, 1 - min_ratio]`
+    pub fn is_one_sided(&self, min_ratio: Decimal) -> bool {
+        self.txns.h24
+            .buy_ratio()
+            .is_some_and(|ratio| ratio < min_ratio || ratio > Decimal::ONE - min_ratio)
+    }
+}
+
 /// Cached price entry with timestamp
 #[derive(Debug, Clone)]
 pub struct CachedPrice {
     pub price: Decimal,
+    pub liquidity: Decimal,
     pub timestamp: chrono::DateTime<chrono::Utc>,
     pub source: String,
 }
@@ -136,49 +573,162 @@ impl CachedPrice {
 pub struct ArbitrageLeg {
     pub from_token: Token,
     pub to_token: Token,
-    pub exchange: Exchange,
+    /// Pool the swap executes in (its exchange supplies the router)
+    pub pool: Pool,
     pub price: Decimal,
     pub liquidity: Decimal,
+    /// Pool reserves of `from_token` and `to_token` (None if unknown)
+    #[serde(default)]
+    pub reserves: Option<(TokenAmount, TokenAmount)>,
 }
 
 impl ArbitrageLeg {
     pub fn new(
         from_token: Token,
         to_token: Token,
-        exchange: Exchange,
+        pool: Pool,
         price: Decimal,
         liquidity: Decimal,
     ) -> Self {
         ArbitrageLeg {
             from_token,
             to_token,
-            exchange,
+            pool,
             price,
             liquidity,
+            reserves: None,
         }
     }
+
+    /// Attach the pool's reserves of `from_token` and `to_token`
+    pub fn with_reserves(mut self, reserve_in: TokenAmount, reserve_out: TokenAmount) -> Self {
+        self.reserves = Some((reserve_in, reserve_out));
+        self
+    }
+
+    /// Amount of `to_token` received for `amount_in`, net of the pool fee
+    pub fn amount_out(&self, amount_in: Decimal) -> Decimal {
+        self.pool.amount_after_fee(amount_in) * self.price
+    }
+}
+
+/// Longest route `ArbitrageRoute::new` accepts
+pub const MAX_ROUTE_HOPS: usize = 4;
+
+/// Reasons a sequence of legs is not a valid route
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum RouteError {
+    #[error("route has no legs")]
+    Empty,
+
+    #[error("route has {hops} hops, more than the maximum of {max}")]
+    TooManyHops { hops: usize, max: usize },
+
+    #[error("leg {leg} starts at {found} but the previous leg ends at {expected}")]
+    Broken { leg: usize, expected: Address, found: Address },
+
+    #[error("route starts at {start} but ends at {end}")]
+    NotClosed { start: Address, end: Address },
+
+    #[error("pool {0} is used more than once")]
+    RepeatedPool(Address),
 }
 
 /// Represents a complete arbitrage route (sequence of trades)
+///
+/// Routes are always non-empty cycles: each leg starts where the previous
+/// one ended, the last leg returns to the starting token, and no pool is
+/// swapped through twice.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "RouteLegs")]
 pub struct ArbitrageRoute {
-    pub legs: Vec<ArbitrageLeg>,
-    pub total_hops: usize,
+    legs: Vec<ArbitrageLeg>,
+}
+
+/// Unvalidated form of a route, so deserialized routes are checked too
+#[derive(Deserialize)]
+struct RouteLegs {
+    legs: Vec<ArbitrageLeg>,
+}
+
+impl TryFrom<RouteLegs> for ArbitrageRoute {
+    type Error = RouteError;
+
+    fn try_from(raw: RouteLegs) -> Result<Self, Self::Error> {
+        ArbitrageRoute::new(raw.legs)
+    }
 }
 
 impl ArbitrageRoute {
-    pub fn new(legs: Vec<ArbitrageLeg>) -> Self {
-        let total_hops = legs.len();
-        ArbitrageRoute { legs, total_hops }
+    /// Build a route of at most `MAX_ROUTE_HOPS` legs
+    pub fn new(legs: Vec<ArbitrageLeg>) -> Result<Self, RouteError> {
+        Self::with_max_hops(legs, MAX_ROUTE_HOPS)
     }
 
-    /// Format the route as a string (e.g., "USDC -> WETH -> METIS -> USDC")
-    pub fn format_path(&self) -> String {
-        if self.legs.is_empty() {
-            return String::new();
+    /// Build a route of at most `max_hops` legs
+    pub fn with_max_hops(legs: Vec<ArbitrageLeg>, max_hops: usize) -> Result<Self, RouteError> {
+        let (Some(first), Some(last)) = (legs.first(), legs.last()) else {
+            return Err(RouteError::Empty);
+        };
+        if legs.len() > max_hops {
+            return Err(RouteError::TooManyHops { hops: legs.len(), max: max_hops });
+        }
+
+        for (i, pair) in legs.windows(2).enumerate() {
+            let (expected, found) = (pair[0].to_token.address, pair[1].from_token.address);
+            if expected != found {
+                return Err(RouteError::Broken { leg: i + 1, expected, found });
+            }
+        }
+        if last.to_token.address != first.from_token.address {
+            return Err(RouteError::NotClosed { start: first.from_token.address, end: last.to_token.address });
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        if let Some(leg) = legs.iter().find(|leg| !seen.insert(leg.pool.address)) {
+            return Err(RouteError::RepeatedPool(leg.pool.address));
+        }
+
+        Ok(ArbitrageRoute { legs })
+    }
+
+    pub fn legs(&self) -> &[ArbitrageLeg] {
+        &self.legs
+    }
+
+    pub fn total_hops(&self) -> usize {
+        self.legs.len()
+    }
+
+    /// Token the route starts and ends in
+    pub fn start_token(&self) -> &Token {
+        &self.legs[0].from_token
+    }
+
+    /// Pools swapped through, in route order
+    pub fn pools(&self) -> impl Iterator<Item = &Pool> {
+        self.legs.iter().map(|leg| &leg.pool)
+    }
+
+    /// Names of the exchanges used, in route order without repeats
+    pub fn exchanges(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for pool in self.pools() {
+            if !names.contains(&pool.exchange.name.as_str()) {
+                names.push(&pool.exchange.name);
+            }
         }
+        names
+    }
+
+    /// Amount of the start token received for `amount_in`, net of pool fees
+    pub fn amount_out(&self, amount_in: Decimal) -> Decimal {
+        self.legs.iter().fold(amount_in, |amount, leg| leg.amount_out(amount))
+    }
 
-        let mut path = vec![self.legs[0].from_token.symbol.clone()];
+    /// Format the route as a string (e.g., "USDC -> WETH -> METIS -> USDC")
+    pub fn format_path(&self) -> String {
+        let mut path = vec![self.start_token().symbol.clone()];
         for leg in &self.legs {
             path.push(leg.to_token.symbol.clone());
         }
@@ -197,6 +747,10 @@ pub struct ArbitrageOpportunity {
     pub gas_cost: Decimal,
     pub profit_percentage: Decimal,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Exact amount swapped into each leg followed by the route's output,
+    /// in base units (None when estimated at mid prices)
+    #[serde(default)]
+    pub swap_amounts: Option<Vec<TokenAmount>>,
 }
 
 impl ArbitrageOpportunity {
@@ -223,30 +777,71 @@ impl ArbitrageOpportunity {
             gas_cost,
             profit_percentage,
             timestamp: chrono::Utc::now(),
+            swap_amounts: None,
         }
     }
+
+    /// Attach the exact amount entering each leg, then the route's output
+    pub fn with_swap_amounts(mut self, amounts: Vec<TokenAmount>) -> Self {
+        self.swap_amounts = Some(amounts);
+        self
+    }
+
+    /// Exact amount of the start token put in, if simulated
+    pub fn exact_input(&self) -> Option<TokenAmount> {
+        self.swap_amounts.as_ref().and_then(|a| a.first().copied())
+    }
+
+    /// Exact amount of the start token received, if simulated
+    pub fn exact_output(&self) -> Option<TokenAmount> {
+        self.swap_amounts.as_ref().and_then(|a| a.last().copied())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::address;
     use rust_decimal_macros::dec;
 
+    #[test]
+    fn test_market_stats_flow_and_age() {
+        let created = chrono::Utc::now() - chrono::Duration::hours(3);
+        let mut stats = MarketStats {
+            pair_created_at: Some(created),
+            ..Default::default()
+        };
+
+        assert!(!stats.is_one_sided(dec!(0.1)));
+        stats.txns.h24 = TxnCounts { buys: 95, sells: 5 };
+        assert_eq!(stats.txns.h24.buy_ratio(), Some(dec!(0.95)));
+        assert!(stats.is_one_sided(dec!(0.1)));
+        assert!(!stats.is_one_sided(dec!(0.05)));
+        assert_eq!(stats.age(created + chrono::Duration::hours(3)), Some(chrono::Duration::hours(3)));
+    }
+
     #[test]
     fn test_token_creation() {
-        let token = Token::new("WETH", "Wrapped Ether", 18, "0x123...");
+        let token = Token::new("WETH", "Wrapped Ether", 18, address(0x12));
         assert_eq!(token.symbol, "WETH");
         assert_eq!(token.decimals, 18);
+        assert_eq!(token.chain_id, METIS_CHAIN_ID);
+
+        // Token caches written before chain ids default to Metis
+        let json = r#"{"symbol":"WETH","name":"Wrapped Ether","decimals":18,"address":"0x0000000000000000000000000000000000000012"}"#;
+        assert_eq!(serde_json::from_str::<Token>(json).unwrap(), token);
+        assert_eq!(token.with_chain_id(42161).chain_id, 42161);
     }
 
     #[test]
     fn test_trading_pair_id() {
-        let base = Token::new("WETH", "Wrapped Ether", 18, "0x123");
-        let quote = Token::new("USDC", "USD Coin", 6, "0x456");
-        let exchange = Exchange::new("netswap", "Metis", "0x789");
+        let base = Token::new("WETH", "Wrapped Ether", 18, address(0x12));
+        let quote = Token::new("USDC", "USD Coin", 6, address(0x45));
+        let exchange = Exchange::new("netswap", METIS_CHAIN_ID).with_router(address(0x78));
+        let pool = Pool::new(address(0xaa), exchange, base.clone(), quote.clone());
 
         let pair = TradingPair::new(
-            base, quote, exchange,
+            base, quote, pool,
             dec!(1800.50),
             dec!(500000),
             dec!(100),
@@ -257,10 +852,130 @@ mod tests {
         assert_eq!(pair.full_id(), "netswap:WETH/USDC");
     }
 
+    #[test]
+    fn test_price_key_distinguishes_pools() {
+        let base = Token::new("WETH", "Wrapped Ether", 18, address(0xab));
+        let quote = Token::new("USDC", "USD Coin", 6, address(0xde));
+        let pool = |pool: Address, exchange: &str| {
+            Pool::new(pool, Exchange::new(exchange, METIS_CHAIN_ID), base.clone(), quote.clone())
+        };
+        let netswap = TradingPair::new(
+            base.clone(), quote.clone(), pool(address(0x11), "netswap"),
+            dec!(1800), dec!(1), dec!(1), dec!(1),
+        );
+        let tethys = TradingPair::new(
+            base.clone(), quote.clone(), pool(address(0x22), "tethys"),
+            dec!(1801), dec!(1), dec!(1), dec!(1),
+        );
+
+        assert_ne!(PriceKey::from_pair(&netswap), PriceKey::from_pair(&tethys));
+        assert_eq!(PriceKey::from_pair(&netswap).pair.token0, address(0xab));
+    }
+
+    #[test]
+    fn test_canonical_orientation() {
+        let weth = Token::new("WETH", "Wrapped Ether", 18, address(0x12));
+        let usdc = Token::new("USDC", "USD Coin", 6, address(0x45));
+        let pool = Pool::new(address(0xaa), Exchange::new("tethys", METIS_CHAIN_ID), weth.clone(), usdc.clone());
+
+        // Listed the other way round: 1 USDC = 0.0005 WETH
+        let inverted = TradingPair::new(usdc.clone(), weth.clone(), pool.clone(), dec!(0.0005), dec!(1), dec!(4000), dec!(2));
+        let listed = TradingPair::new(weth.clone(), usdc.clone(), pool, dec!(2000), dec!(1), dec!(2), dec!(4000));
+        assert_eq!(inverted.pair_key(), listed.pair_key());
+        assert_eq!(PriceKey::from_pair(&inverted), PriceKey::from_pair(&listed));
+
+        assert!(!inverted.is_canonical());
+        assert_eq!(inverted.canonical_price(), dec!(2000));
+        let canonical = inverted.canonical();
+        assert_eq!((canonical.pair_id(), canonical.price), ("WETH/USDC".to_string(), dec!(2000)));
+        assert_eq!((canonical.reserve_base, canonical.reserve_quote), (dec!(2), dec!(4000)));
+
+        let key = listed.pair_key();
+        assert_eq!(key.orient(dec!(2000), usdc.address), Some(dec!(0.0005)));
+        assert_eq!(key.orient(dec!(2000), address(0x99)), None);
+    }
+
+    #[test]
+    fn test_pool_orders_tokens_by_address() {
+        let usdc_address: Address = "0xEA32A96608495e54156Ae48931A7c20f0dcc1a21".parse().unwrap();
+        let usdc = Token::new("m.USDC", "USD Coin", 6, usdc_address);
+        let weth = Token::new("WETH", "Wrapped Ether", 18, "0x420000000000000000000000000000000000000A".parse().unwrap());
+        let tethys = known_metis_exchanges().remove(1);
+
+        let pool = Pool::new("0x5ae3ee7fbb3cb28c17e7adc3a6ae605ae2465091".parse().unwrap(), tethys, usdc, weth);
+        assert_eq!(pool.token0.symbol, "WETH");
+        assert_eq!(pool.token1.symbol, "m.USDC");
+        assert!(pool.contains("0xea32a96608495e54156ae48931a7c20f0dcc1a21".parse().unwrap()));
+        assert_eq!(pool.exchange.router_address, Some(TETHYS_ROUTER.parse().unwrap()));
+        assert_eq!((pool.fee_bps, pool.kind), (TETHYS_FEE_BPS, PoolKind::ConstantProduct));
+    }
+
+    #[test]
+    fn test_fees_adjust_prices_and_outputs() {
+        let base = Token::new("WETH", "Wrapped Ether", 18, address(0x12));
+        let quote = Token::new("USDC", "USD Coin", 6, address(0x45));
+        let pool = Pool::new(address(0xaa), Exchange::new("netswap", METIS_CHAIN_ID), base.clone(), quote.clone());
+        let pair = TradingPair::new(base.clone(), quote.clone(), pool.clone(), dec!(2000), dec!(1), dec!(1), dec!(1));
+
+        assert_eq!(pool.fee_rate(), dec!(0.003));
+        assert_eq!(pair.sell_price(), dec!(1994));
+        assert_eq!(pair.buy_price().round_dp(4), dec!(2006.0181));
+
+        // A per-pool override replaces the exchange's tier
+        let custom = pool.with_fee_bps(100);
+        let leg = ArbitrageLeg::new(base, quote, custom, dec!(2000), dec!(1));
+        assert_eq!(leg.amount_out(dec!(2)), dec!(3960));
+    }
+
+    #[test]
+    fn test_route_validation() {
+        let usdc = Token::new("USDC", "USD Coin", 6, address(0x01));
+        let weth = Token::new("WETH", "Wrapped Ether", 18, address(0x02));
+        let metis = Token::new("METIS", "Metis Token", 18, address(0x03));
+        let leg = |from: &Token, to: &Token, pool: u8, exchange: &str, price| {
+            let pool = Pool::new(address(pool), Exchange::new(exchange, METIS_CHAIN_ID), from.clone(), to.clone());
+            ArbitrageLeg::new(from.clone(), to.clone(), pool, price, dec!(1))
+        };
+
+        let route = ArbitrageRoute::new(vec![
+            leg(&usdc, &weth, 0xa1, "netswap", dec!(0.0005)),
+            leg(&weth, &metis, 0xa2, "tethys", dec!(40)),
+            leg(&metis, &usdc, 0xa3, "netswap", dec!(51)),
+        ])
+        .unwrap();
+        assert_eq!(route.format_path(), "USDC -> WETH -> METIS -> USDC");
+        assert_eq!(route.start_token().symbol, "USDC");
+        assert_eq!(route.exchanges(), vec!["netswap", "tethys"]);
+        assert_eq!(route.pools().map(|p| p.address).collect::<Vec<_>>(), vec![address(0xa1), address(0xa2), address(0xa3)]);
+        assert!(route.amount_out(dec!(1000)) > dec!(1000));
+
+        let json = serde_json::to_string(&route).unwrap();
+        assert_eq!(serde_json::from_str::<ArbitrageRoute>(&json).unwrap().total_hops(), 3);
+
+        assert_eq!(ArbitrageRoute::new(vec![]).unwrap_err(), RouteError::Empty);
+        assert_eq!(
+            ArbitrageRoute::new(vec![leg(&usdc, &weth, 0xa1, "netswap", dec!(1)), leg(&metis, &usdc, 0xa3, "netswap", dec!(1))]).unwrap_err(),
+            RouteError::Broken { leg: 1, expected: address(0x02), found: address(0x03) }
+        );
+        assert_eq!(
+            ArbitrageRoute::new(vec![leg(&usdc, &weth, 0xa1, "netswap", dec!(1))]).unwrap_err(),
+            RouteError::NotClosed { start: address(0x01), end: address(0x02) }
+        );
+        assert_eq!(
+            ArbitrageRoute::new(vec![leg(&usdc, &weth, 0xa1, "netswap", dec!(1)), leg(&weth, &usdc, 0xa1, "netswap", dec!(1))]).unwrap_err(),
+            RouteError::RepeatedPool(address(0xa1))
+        );
+        assert_eq!(
+            ArbitrageRoute::with_max_hops(route.legs().to_vec(), 2).unwrap_err(),
+            RouteError::TooManyHops { hops: 3, max: 2 }
+        );
+    }
+
     #[test]
     fn test_cached_price_staleness() {
         let cached = CachedPrice {
             price: dec!(1800),
+            liquidity: dec!(500000),
             timestamp: chrono::Utc::now() - chrono::Duration::seconds(120),
             source: "test".to_string(),
         };
@@ -0,0 +1,388 @@
+//! multicall.rs - Multicall3 batching for on-chain view calls
+//!
+//! Groups arbitrary view calls into `aggregate3` batches so a scan
+//! costs a handful of RPC round-trips instead of one per call, with a bounded
+//! number of batches in flight. Individual calls may revert without failing
+//! the rest of their batch.
+
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::abi::{self, AbiError, WORD_SIZE};
+use crate::address::Address;
+use crate::chain::Chain;
+use crate::rpc::{BlockTag, RpcClient};
+
+/// Canonical Multicall3 deployment (same address on Metis and most EVM chains)
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+/// `aggregate3((address,bool,bytes)[])`
+pub const SELECTOR_AGGREGATE3: [u8; 4] = [0x82, 0xad, 0x56, 0xcb];
+
+/// Default number of calls packed into a single `aggregate3` request
+pub const DEFAULT_BATCH_SIZE: usize = 100;
+
+/// Default number of `aggregate3` requests in flight at once
+pub const DEFAULT_MAX_CONCURRENT_BATCHES: usize = 4;
+
+/// A single view call to be batched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    pub target: Address,
+    pub call_data: Vec<u8>,
+    pub allow_failure: bool,
+}
+
+impl Call {
+    /// A call that is allowed to revert without failing its batch
+    pub fn new(target: Address, call_data: impl Into<Vec<u8>>) -> Self {
+        Call {
+            target,
+            call_data: call_data.into(),
+            allow_failure: true,
+        }
+    }
+}
+
+/// Why an individual call produced no usable result
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum CallFailure {
+    #[error("call reverted")]
+    Reverted(Vec<u8>),
+
+    #[error("batch request failed: {0}")]
+    Batch(String),
+
+    #[error("could not decode result: {0}")]
+    Decode(#[from] AbiError),
+}
+
+/// Raw outcome of one call
+pub type CallResult = Result<Vec<u8>, CallFailure>;
+
+/// Multicall3 client batching calls through `aggregate3`
+#[derive(Debug, Clone)]
+pub struct Multicall {
+    rpc: RpcClient,
+    address: Address,
+    batch_size: usize,
+    max_concurrent_batches: usize,
+}
+
+impl Multicall {
+    /// Create a client using the canonical Multicall3 address
+    pub fn new(rpc: RpcClient) -> Self {
+        Multicall {
+            rpc,
+            address: MULTICALL3_ADDRESS.parse().expect("Multicall3 address is checksummed"),
+            batch_size: DEFAULT_BATCH_SIZE,
+            max_concurrent_batches: DEFAULT_MAX_CONCURRENT_BATCHES,
+        }
+    }
+
+    /// Client for the chain's Multicall3 deployment, if it has one
+    pub fn for_chain(rpc: RpcClient, chain: &Chain) -> Option<Self> {
+        chain.multicall3.map(|address| Self::new(rpc).with_address(address))
+    }
+
+    /// Use a Multicall3 deployment at a different address
+    pub fn with_address(mut self, address: Address) -> Self {
+        self.address = address;
+        self
+    }
+
+    /// Set how many `aggregate3` requests may be in flight at once
+    pub fn with_max_concurrent_batches(mut self, max: usize) -> Self {
+        self.max_concurrent_batches = max.max(1);
+        self
+    }
+
+    /// Set the maximum number of calls per `aggregate3` request
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Maximum number of calls per `aggregate3` request
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Execute all calls, batched and in parallel (at most
+    /// `max_concurrent_batches` requests at a time), returning results keyed
+    /// by request. A failed batch marks only its own calls as failed.
+    pub async fn aggregate<K>(&self, calls: Vec<(K, Call)>, block: BlockTag) -> HashMap<K, CallResult>
+    where
+        K: Eq + Hash,
+    {
+        let mut keys: Vec<Option<K>> = Vec::with_capacity(calls.len());
+        let mut chunks: Vec<Vec<Call>> = Vec::new();
+
+        for (i, (key, call)) in calls.into_iter().enumerate() {
+            if i % self.batch_size == 0 {
+                chunks.push(Vec::with_capacity(self.batch_size));
+            }
+            keys.push(Some(key));
+            chunks.last_mut().expect("chunk pushed above").push(call);
+        }
+
+        debug!("Multicall: {} calls in {} batches", keys.len(), chunks.len());
+
+        let permits = Arc::new(Semaphore::new(self.max_concurrent_batches));
+        let mut tasks = JoinSet::new();
+        for (index, chunk) in chunks.into_iter().enumerate() {
+            let rpc = self.rpc.clone();
+            let address = self.address;
+            let permits = permits.clone();
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await.expect("multicall semaphore is never closed");
+                let count = chunk.len();
+                (index, count, execute_batch(&rpc, address, &chunk, block).await)
+            });
+        }
+
+        let mut results = HashMap::with_capacity(keys.len());
+        while let Some(joined) = tasks.join_next().await {
+            let (index, count, outcome) = joined.expect("multicall batch task panicked");
+            let start = index * self.batch_size;
+
+            let outcomes: Vec<CallResult> = match outcome {
+                Ok(outcomes) => outcomes,
+                Err(e) => {
+                    warn!("Multicall batch {} failed: {}", index, e);
+                    vec![Err(CallFailure::Batch(e)); count]
+                }
+            };
+
+            for (offset, result) in outcomes.into_iter().enumerate() {
+                if let Some(key) = keys.get_mut(start + offset).and_then(Option::take) {
+                    results.insert(key, result);
+                }
+            }
+        }
+        results
+    }
+
+    /// Execute all calls and decode each successful result with `decode`
+    pub async fn aggregate_decoded<K, T, F>(
+        &self,
+        calls: Vec<(K, Call)>,
+        block: BlockTag,
+        decode: F,
+    ) -> HashMap<K, Result<T, CallFailure>>
+    where
+        K: Eq + Hash,
+        F: Fn(&K, &[u8]) -> Result<T, AbiError>,
+    {
+        self.aggregate(calls, block)
+            .await
+            .into_iter()
+            .map(|(key, result)| {
+                let decoded = result.and_then(|data| decode(&key, &data).map_err(CallFailure::from));
+                (key, decoded)
+            })
+            .collect()
+    }
+}
+
+/// Send one `aggregate3` request and split its results per call
+async fn execute_batch(
+    rpc: &RpcClient,
+    address: Address,
+    calls: &[Call],
+    block: BlockTag,
+) -> Result<Vec<CallResult>, String> {
+    let data = encode_aggregate3(calls);
+    let output = rpc.eth_call(&address.to_checksum(), &data, block).await.map_err(|e| e.to_string())?;
+    let decoded = decode_aggregate3(&output).map_err(|e| e.to_string())?;
+
+    if decoded.len() != calls.len() {
+        return Err(format!("expected {} results, got {}", calls.len(), decoded.len()));
+    }
+
+    Ok(decoded
+        .into_iter()
+        .map(|(success, data)| if success { Ok(data) } else { Err(CallFailure::Reverted(data)) })
+        .collect())
+}
+
+/// Pad a byte length up to a whole number of ABI words
+fn padded_len(len: usize) -> usize {
+    len.div_ceil(WORD_SIZE) * WORD_SIZE
+}
+
+/// ABI-encode an `aggregate3` call
+pub fn encode_aggregate3(calls: &[Call]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&SELECTOR_AGGREGATE3);
+    out.extend_from_slice(&abi::encode_u128(WORD_SIZE as u128));
+    out.extend_from_slice(&abi::encode_u128(calls.len() as u128));
+
+    // Each Call3 tuple is dynamic: head offsets first, then the tuples
+    let mut tuples = Vec::with_capacity(calls.len());
+    for call in calls {
+        let mut tuple = Vec::new();
+        tuple.extend_from_slice(&[0u8; WORD_SIZE - 20]);
+        tuple.extend_from_slice(call.target.as_bytes());
+        tuple.extend_from_slice(&abi::encode_u128(u128::from(call.allow_failure)));
+        tuple.extend_from_slice(&abi::encode_u128(3 * WORD_SIZE as u128));
+        tuple.extend_from_slice(&abi::encode_u128(call.call_data.len() as u128));
+        tuple.extend_from_slice(&call.call_data);
+        tuple.resize(4 * WORD_SIZE + padded_len(call.call_data.len()), 0);
+        tuples.push(tuple);
+    }
+
+    let mut offset = calls.len() * WORD_SIZE;
+    for tuple in &tuples {
+        out.extend_from_slice(&abi::encode_u128(offset as u128));
+        offset += tuple.len();
+    }
+    for tuple in tuples {
+        out.extend_from_slice(&tuple);
+    }
+    out
+}
+
+/// Decode the `(bool success, bytes returnData)[]` returned by `aggregate3`
+pub fn decode_aggregate3(data: &[u8]) -> Result<Vec<(bool, Vec<u8>)>, AbiError> {
+    let array_start = read_offset(abi::word(data, 0)?)?;
+    let array = data
+        .get(array_start..)
+        .ok_or(AbiError::TooShort { expected: array_start, actual: data.len() })?;
+    let len = read_offset(abi::word(array, 0)?)?;
+    let items = &array[WORD_SIZE..];
+
+    (0..len)
+        .map(|i| {
+            let tuple_start = read_offset(abi::word(items, i)?)?;
+            let tuple = items
+                .get(tuple_start..)
+                .ok_or(AbiError::TooShort { expected: tuple_start, actual: items.len() })?;
+
+            let success = abi::decode_u128(abi::word(tuple, 0)?)? != 0;
+            // Offsets and lengths come from the response; a hostile or
+            // corrupt one must not overflow the bounds arithmetic
+            let bytes_start = read_offset(abi::word(tuple, 1)?)?;
+            let body_start = bytes_start.checked_add(WORD_SIZE).ok_or(AbiError::Overflow("usize"))?;
+            let bytes_len = read_offset(
+                tuple
+                    .get(bytes_start..body_start)
+                    .ok_or(AbiError::TooShort { expected: body_start, actual: tuple.len() })?,
+            )?;
+            let body_end = body_start.checked_add(bytes_len).ok_or(AbiError::Overflow("usize"))?;
+            let body = tuple
+                .get(body_start..body_end)
+                .ok_or(AbiError::TooShort { expected: body_end, actual: tuple.len() })?;
+
+            Ok((success, body.to_vec()))
+        })
+        .collect()
+}
+
+fn read_offset(word: &[u8]) -> Result<usize, AbiError> {
+    usize::try_from(abi::decode_u128(word)?).map_err(|_| AbiError::Overflow("usize"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{serve_aggregate3, MockRpcServer};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const TOKEN_A: &str = "0x0000000000000000000000000000000000000aaa";
+    const TOKEN_B: &str = "0x0000000000000000000000000000000000000bbb";
+
+    fn address(s: &str) -> Address {
+        s.parse().unwrap()
+    }
+
+    /// Answers decimals() for TOKEN_A (18) and TOKEN_B (6); reverts otherwise
+    fn erc20_call(target: &str, data: &[u8]) -> Option<Vec<u8>> {
+        match (target, data) {
+            (TOKEN_A, d) if d == abi::SELECTOR_DECIMALS => Some(abi::encode_u128(18).to_vec()),
+            (TOKEN_B, d) if d == abi::SELECTOR_DECIMALS => Some(abi::encode_u128(6).to_vec()),
+            _ => None,
+        }
+    }
+
+    async fn mock_multicall(batches: Arc<AtomicUsize>) -> MockRpcServer {
+        MockRpcServer::start(move |method, params| {
+            assert_eq!(method, "eth_call");
+            assert_eq!(params[0]["to"].as_str().unwrap(), MULTICALL3_ADDRESS);
+            batches.fetch_add(1, Ordering::SeqCst);
+            let data = abi::decode_hex(params[0]["data"].as_str().unwrap()).unwrap();
+            Ok(json!(abi::encode_hex(&serve_aggregate3(&data, erc20_call))))
+        })
+        .await
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let calls = vec![
+            Call::new(address(TOKEN_A), abi::SELECTOR_DECIMALS),
+            Call::new(address(TOKEN_B), abi::SELECTOR_SYMBOL),
+        ];
+        let encoded = encode_aggregate3(&calls);
+        assert_eq!(encoded[..4], SELECTOR_AGGREGATE3);
+
+        let response = serve_aggregate3(&encoded, erc20_call);
+        let decoded = decode_aggregate3(&response).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert!(decoded[0].0);
+        assert_eq!(abi::decode_u8(&decoded[0].1).unwrap(), 18);
+        assert!(!decoded[1].0);
+    }
+
+    #[test]
+    fn test_decode_rejects_overflowing_offsets() {
+        // One result whose returnData offset points past usize::MAX
+        let mut response = Vec::new();
+        for word in [WORD_SIZE as u128, 1, WORD_SIZE as u128, 1, usize::MAX as u128 - 8] {
+            response.extend_from_slice(&abi::encode_u128(word));
+        }
+        assert_eq!(decode_aggregate3(&response), Err(AbiError::Overflow("usize")));
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_batches_and_tolerates_failures() {
+        let batches = Arc::new(AtomicUsize::new(0));
+        let server = mock_multicall(batches.clone()).await;
+        let multicall = Multicall::new(RpcClient::new(&server.url))
+            .with_batch_size(2)
+            .with_max_concurrent_batches(1);
+
+        let calls = vec![
+            ("a", Call::new(address(TOKEN_A), abi::SELECTOR_DECIMALS)),
+            ("b", Call::new(address(TOKEN_B), abi::SELECTOR_DECIMALS)),
+            ("bad", Call::new(address(TOKEN_A), abi::SELECTOR_SYMBOL)),
+        ];
+
+        let results = multicall
+            .aggregate_decoded(calls, BlockTag::Latest, |_, data| abi::word(data, 0).and_then(abi::decode_u8))
+            .await;
+
+        assert_eq!(batches.load(Ordering::SeqCst), 2);
+        assert_eq!(results["a"], Ok(18));
+        assert_eq!(results["b"], Ok(6));
+        assert!(matches!(results["bad"], Err(CallFailure::Reverted(_))));
+    }
+
+    #[tokio::test]
+    async fn test_failed_batch_marks_its_calls() {
+        let server = MockRpcServer::start(|_, _| Err((-32000, "out of gas".to_string()))).await;
+        let multicall = Multicall::new(RpcClient::new(&server.url));
+
+        let results = multicall
+            .aggregate(vec![(1, Call::new(address(TOKEN_A), abi::SELECTOR_DECIMALS))], BlockTag::Latest)
+            .await;
+
+        assert!(matches!(results[&1], Err(CallFailure::Batch(_))));
+    }
+}
@@ -0,0 +1,530 @@
+//! pool_stream.rs - Event-driven pool updates over WebSocket
+//!
+//! Subscribes to UniswapV2 `Sync` and `Swap` logs for tracked pools
+//! via `eth_subscribe`, applies reserve updates to a shared pair set as they
+//! arrive, and replays missed logs with `eth_getLogs` after a reconnect.
+
+use futures_util::{SinkExt, StreamExt};
+use log::{debug, info, warn};
+use primitive_types::U256;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::{mpsc, RwLock};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::abi::{self, AbiError};
+use crate::address::Address;
+use crate::models::TradingPair;
+
+/// `Sync(uint112 reserve0, uint112 reserve1)`
+pub const SYNC_TOPIC: &str = "0x1c411e9a96e071241c2f21f7726b17ae89e3cab4c78be50e062b03a9fffbbad1";
+
+/// `Swap(address indexed sender, uint amount0In, uint amount1In, uint amount0Out, uint amount1Out, address indexed to)`
+pub const SWAP_TOPIC: &str = "0xd78ad95fa46c994b6551d0da85fc275fe613ce37657fb8d5e3d130840159d822";
+
+/// Default delay before reconnecting after the socket drops
+pub const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+const SUBSCRIBE_ID: u64 = 1;
+const CATCH_UP_ID: u64 = 2;
+
+/// Errors that end a streaming session
+#[derive(Debug, Error)]
+pub enum StreamError {
+    #[error("websocket error: {0}")]
+    WebSocket(#[from] tokio_tungstenite::tungstenite::Error),
+
+    #[error("subscription rejected: {0}")]
+    Subscription(String),
+
+    #[error("connection closed by server")]
+    Closed,
+
+    #[error("event receiver dropped")]
+    ReceiverDropped,
+}
+
+/// An `eth_subscribe`/`eth_getLogs` log entry
+#[derive(Debug, Clone, Deserialize)]
+pub struct Log {
+    pub address: Address,
+    pub topics: Vec<String>,
+    pub data: String,
+    #[serde(rename = "blockNumber")]
+    pub block_number: Option<String>,
+    /// Position of the log within its block
+    #[serde(rename = "logIndex", default)]
+    pub log_index: Option<String>,
+    #[serde(default)]
+    pub removed: bool,
+}
+
+/// Decoded pool event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PoolEvent {
+    Sync {
+        pool: Address,
+        reserve0: u128,
+        reserve1: u128,
+        block: u64,
+        log_index: u64,
+    },
+    Swap {
+        pool: Address,
+        amount0_in: u128,
+        amount1_in: u128,
+        amount0_out: u128,
+        amount1_out: u128,
+        block: u64,
+        log_index: u64,
+    },
+}
+
+impl PoolEvent {
+    /// Decode a log, returning `None` for unrelated or pending logs
+    pub fn from_log(log: &Log) -> Result<Option<Self>, AbiError> {
+        let Some(block) = log.block_number.as_deref() else {
+            return Ok(None);
+        };
+        let block = abi::parse_quantity(block)?;
+        let log_index = log.log_index.as_deref().map(abi::parse_quantity).transpose()?.unwrap_or(0);
+        let pool = log.address;
+        let data = abi::decode_hex(&log.data)?;
+        let uint = |i| abi::word(&data, i).and_then(abi::decode_u128);
+
+        match log.topics.first().map(|t| t.to_lowercase()) {
+            Some(t) if t == SYNC_TOPIC => Ok(Some(PoolEvent::Sync {
+                pool,
+                reserve0: uint(0)?,
+                reserve1: uint(1)?,
+                block,
+                log_index,
+            })),
+            Some(t) if t == SWAP_TOPIC => Ok(Some(PoolEvent::Swap {
+                pool,
+                amount0_in: uint(0)?,
+                amount1_in: uint(1)?,
+                amount0_out: uint(2)?,
+                amount1_out: uint(3)?,
+                block,
+                log_index,
+            })),
+            _ => Ok(None),
+        }
+    }
+
+    /// Pool the event was emitted by
+    pub fn pool(&self) -> Address {
+        match self {
+            PoolEvent::Sync { pool, .. } | PoolEvent::Swap { pool, .. } => *pool,
+        }
+    }
+
+    /// Block the event was included in
+    pub fn block(&self) -> u64 {
+        match self {
+            PoolEvent::Sync { block, .. } | PoolEvent::Swap { block, .. } => *block,
+        }
+    }
+
+    /// Block and index within it, the order logs were emitted in
+    pub fn position(&self) -> (u64, u64) {
+        match self {
+            PoolEvent::Sync { block, log_index, .. } | PoolEvent::Swap { block, log_index, .. } => (*block, *log_index),
+        }
+    }
+}
+
+/// Streams pool logs over WebSocket and keeps a shared pair set current
+///
+/// Intended for pairs produced by `OnChainPriceFeed` (see
+/// `OnChainPriceFeed::pairs_handle`): `Sync` updates overwrite reserves,
+/// price, liquidity (in quote units) and `reserve_block`. Each pool's logs
+/// are applied once, in (block, log index) order, so the catch-up query can
+/// overlap what was already seen.
+#[derive(Debug)]
+pub struct PoolStream {
+    ws_url: String,
+    pairs: Arc<RwLock<Vec<TradingPair>>>,
+    pools: Vec<Address>,
+    last_block: Option<u64>,
+    /// Position of the last log applied per pool
+    applied: HashMap<Address, (u64, u64)>,
+    reconnect_delay: Duration,
+}
+
+impl PoolStream {
+    /// Create a stream updating `pairs`; tracked pools default to the pools
+    /// present in `pairs` when each session starts
+    pub fn new(ws_url: &str, pairs: Arc<RwLock<Vec<TradingPair>>>) -> Self {
+        PoolStream {
+            ws_url: ws_url.to_string(),
+            pairs,
+            pools: Vec::new(),
+            last_block: None,
+            applied: HashMap::new(),
+            reconnect_delay: DEFAULT_RECONNECT_DELAY,
+        }
+    }
+
+    /// Track an explicit set of pool addresses
+    pub fn with_pools(mut self, pools: Vec<Address>) -> Self {
+        self.pools = pools;
+        self
+    }
+
+    /// Set the delay before reconnecting after a dropped connection
+    pub fn with_reconnect_delay(mut self, delay: Duration) -> Self {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    /// Highest block seen so far
+    pub fn last_block(&self) -> Option<u64> {
+        self.last_block
+    }
+
+    /// Run until the event receiver is dropped, reconnecting as needed
+    pub async fn run(&mut self, events: mpsc::Sender<PoolEvent>) {
+        loop {
+            match self.run_session(&events).await {
+                Err(StreamError::ReceiverDropped) => return,
+                Err(e) => warn!("Pool stream disconnected: {}", e),
+                Ok(()) => {}
+            }
+
+            if events.is_closed() {
+                return;
+            }
+            tokio::time::sleep(self.reconnect_delay).await;
+            info!("Reconnecting pool stream to {}...", self.ws_url);
+        }
+    }
+
+    /// Run a single connection: subscribe, catch up, then apply live logs
+    pub async fn run_session(&mut self, events: &mpsc::Sender<PoolEvent>) -> Result<(), StreamError> {
+        let pools = self.tracked_pools().await;
+        if self.last_block.is_none() {
+            // Replay from the oldest snapshot so no pool misses an update;
+            // a snapshot already includes every log of its block
+            let pairs = self.pairs.read().await;
+            self.last_block = pairs.iter().filter_map(|p| p.reserve_block).min();
+            for pair in pairs.iter() {
+                if let Some(block) = pair.reserve_block {
+                    self.applied.entry(pair.pool.address).or_insert((block, u64::MAX));
+                }
+            }
+        }
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(self.ws_url.as_str()).await?;
+        let filter = json!({ "address": pools, "topics": [[SYNC_TOPIC, SWAP_TOPIC]] });
+
+        socket.send(Message::Text(json!({
+            "jsonrpc": "2.0",
+            "id": SUBSCRIBE_ID,
+            "method": "eth_subscribe",
+            "params": ["logs", filter],
+        }).to_string())).await?;
+
+        if let Some(last) = self.last_block {
+            let mut catch_up = filter.clone();
+            // Later logs of the last block may not have arrived yet; ones
+            // already applied are dropped by position
+            catch_up["fromBlock"] = json!(abi::format_quantity(last));
+            catch_up["toBlock"] = json!("latest");
+            socket.send(Message::Text(json!({
+                "jsonrpc": "2.0",
+                "id": CATCH_UP_ID,
+                "method": "eth_getLogs",
+                "params": [catch_up],
+            }).to_string())).await?;
+        }
+
+        info!("Pool stream subscribed to {} pools", pools.len());
+
+        while let Some(message) = socket.next().await {
+            let text = match message? {
+                Message::Text(text) => text,
+                Message::Close(_) => return Err(StreamError::Closed),
+                _ => continue,
+            };
+            let Ok(value) = serde_json::from_str::<Value>(&text) else {
+                debug!("Ignoring non-JSON message: {}", text);
+                continue;
+            };
+
+            match value["id"].as_u64() {
+                Some(SUBSCRIBE_ID) => {
+                    if let Some(err) = value.get("error") {
+                        return Err(StreamError::Subscription(err.to_string()));
+                    }
+                    debug!("Subscription id: {}", value["result"]);
+                }
+                Some(CATCH_UP_ID) => {
+                    let logs: Vec<Log> = serde_json::from_value(value["result"].clone()).unwrap_or_default();
+                    debug!("Catch-up returned {} logs", logs.len());
+                    for log in &logs {
+                        self.handle_log(log, events).await?;
+                    }
+                }
+                _ if value["method"] == "eth_subscription" => {
+                    match serde_json::from_value::<Log>(value["params"]["result"].clone()) {
+                        Ok(log) => self.handle_log(&log, events).await?,
+                        Err(e) => debug!("Ignoring malformed log: {}", e),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Err(StreamError::Closed)
+    }
+
+    /// Pools to subscribe to for this session
+    async fn tracked_pools(&self) -> Vec<Address> {
+        if !self.pools.is_empty() {
+            return self.pools.clone();
+        }
+        self.pairs.read().await.iter()
+            .map(|p| p.pool.address)
+            .collect()
+    }
+
+    /// Decode a log, apply it and forward it to `events`
+    async fn handle_log(&mut self, log: &Log, events: &mpsc::Sender<PoolEvent>) -> Result<(), StreamError> {
+        if log.removed {
+            debug!("Skipping removed (reorged) log from {}", log.address);
+            return Ok(());
+        }
+
+        let event = match PoolEvent::from_log(log) {
+            Ok(Some(event)) => event,
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                warn!("Could not decode log from {}: {}", log.address, e);
+                return Ok(());
+            }
+        };
+
+        let pool = event.pool();
+        if self.applied.get(&pool).is_some_and(|applied| *applied >= event.position()) {
+            debug!("Skipping already applied log from {} at {:?}", pool, event.position());
+            return Ok(());
+        }
+        self.applied.insert(pool, event.position());
+
+        self.last_block = Some(self.last_block.map_or(event.block(), |b| b.max(event.block())));
+        if let PoolEvent::Sync { pool, reserve0, reserve1, block, .. } = &event {
+            self.apply_sync(*pool, *reserve0, *reserve1, *block).await;
+        }
+
+        events.send(event).await.map_err(|_| StreamError::ReceiverDropped)
+    }
+
+    /// Overwrite a pair's reserves from a `Sync` event
+    async fn apply_sync(&self, pool: Address, reserve0: u128, reserve1: u128, block: u64) {
+        let mut pairs = self.pairs.write().await;
+        let Some(pair) = pairs.iter_mut().find(|p| p.pool.address == pool) else {
+            return;
+        };
+        if pair.reserve_block.is_some_and(|current| current > block) {
+            return; // Older than what we already hold
+        }
+
+        let base_is_token0 = pair.pool.token0.address == pair.base_token.address;
+        let (raw_base, raw_quote) = if base_is_token0 { (reserve0, reserve1) } else { (reserve1, reserve0) };
+        let raw_base = pair.base_token.amount(U256::from(raw_base));
+        let raw_quote = pair.quote_token.amount(U256::from(raw_quote));
+
+        if raw_base.is_zero() {
+            return;
+        }
+        // Display values are rounded to fit a Decimal, while raw_reserves
+        // keep the exact amounts; dust pools can imply prices beyond it
+        let (Some(reserve_base), Some(reserve_quote), Some(price)) =
+            (raw_base.to_decimal_lossy(), raw_quote.to_decimal_lossy(), raw_quote.ratio_lossy(&raw_base))
+        else {
+            warn!("Sync reserves for {} overflow the price ({} / {})", pool, raw_quote, raw_base);
+            return;
+        };
+
+        pair.reserve_base = reserve_base;
+        pair.reserve_quote = reserve_quote;
+        pair.raw_reserves = Some((raw_base, raw_quote));
+        pair.price = price;
+        pair.liquidity = reserve_quote.saturating_mul(Decimal::from(2));
+        pair.reserve_block = Some(block);
+        pair.updated_at = Some(chrono::Utc::now());
+        debug!("Sync {} @ {}: price {}", pair.full_id(), block, pair.price);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::METIS_CHAIN_ID;
+    use crate::models::{Exchange, Pool, Token};
+    use rust_decimal_macros::dec;
+    use tokio::net::TcpListener;
+
+    const POOL: &str = "0x5ae3ee7fbb3cb28c17e7adc3a6ae605ae2465091";
+
+    fn weth_usdc_pair() -> TradingPair {
+        let weth = Token::new("WETH", "Wrapped Ether", 18, "0x420000000000000000000000000000000000000a".parse().unwrap());
+        let usdc = Token::new("m.USDC", "USD Coin", 6, "0xea32a96608495e54156ae48931a7c20f0dcc1a21".parse().unwrap());
+        TradingPair::new(
+            weth.clone(),
+            usdc.clone(),
+            Pool::new(POOL.parse().unwrap(), Exchange::new("netswap", METIS_CHAIN_ID), weth, usdc),
+            dec!(1850),
+            dec!(37000),
+            dec!(10),
+            dec!(18500),
+        )
+        .with_reserve_block(1000)
+    }
+
+    fn sync_log(block: u64, log_index: u64, reserve0: u128, reserve1: u128) -> Value {
+        let mut data = abi::encode_u128(reserve0).to_vec();
+        data.extend_from_slice(&abi::encode_u128(reserve1));
+        json!({
+            "address": POOL,
+            "topics": [SYNC_TOPIC],
+            "data": abi::encode_hex(&data),
+            "blockNumber": abi::format_quantity(block),
+            "logIndex": abi::format_quantity(log_index),
+        })
+    }
+
+    #[test]
+    fn test_decode_swap_log() {
+        let data: Vec<u8> = [1u128, 0, 0, 2].iter().flat_map(|v| abi::encode_u128(*v)).collect();
+        let log: Log = serde_json::from_value(json!({
+            "address": POOL,
+            "topics": [SWAP_TOPIC],
+            "data": abi::encode_hex(&data),
+            "blockNumber": "0x10",
+        }))
+        .unwrap();
+
+        let event = PoolEvent::from_log(&log).unwrap().unwrap();
+        assert_eq!(event.block(), 16);
+        assert!(matches!(event, PoolEvent::Swap { amount0_in: 1, amount1_out: 2, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_sync_reserves_outside_decimal_range() {
+        let pairs = Arc::new(RwLock::new(vec![weth_usdc_pair()]));
+        let stream = PoolStream::new("ws://127.0.0.1:1", pairs.clone());
+
+        // 1 wei WETH against 10^23 USDC: the price does not fit a Decimal
+        stream.apply_sync(POOL.parse().unwrap(), 1, 100_000_000_000_000_000_000_000_000_000, 1001).await;
+        let pair = pairs.read().await[0].clone();
+        assert_eq!((pair.price, pair.reserve_block), (dec!(1850), Some(1000)));
+
+        // 10^12 WETH plus one wei needs more digits than a Decimal holds:
+        // rounded for display, exact in the raw reserves
+        let weth = 1_000_000_000_000_000_000_000_000_000_001;
+        stream.apply_sync(POOL.parse().unwrap(), weth, 1_850_000_000_000_000_000_000, 1002).await;
+        let pair = pairs.read().await[0].clone();
+        assert_eq!((pair.price.round_dp(6), pair.reserve_block), (dec!(1850), Some(1002)));
+        assert_eq!(pair.reserve_base, dec!(1000000000000));
+        assert_eq!(pair.raw_reserves.unwrap().0.raw(), U256::from(weth));
+    }
+
+    /// WebSocket stand-in: the first connection pushes one live Sync and
+    /// drops; the second answers the catch-up query with that Sync and two
+    /// later ones.
+    async fn mock_ws_node() -> (String, mpsc::Receiver<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let (requests_tx, requests_rx) = mpsc::channel(16);
+
+        tokio::spawn(async move {
+            for connection in 0.. {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                let requests = requests_tx.clone();
+
+                while let Some(Ok(Message::Text(text))) = ws.next().await {
+                    let request: Value = serde_json::from_str(&text).unwrap();
+                    requests.send(request.clone()).await.unwrap();
+
+                    match request["method"].as_str().unwrap() {
+                        "eth_subscribe" => {
+                            let ack = json!({ "jsonrpc": "2.0", "id": request["id"], "result": "0xsub" });
+                            ws.send(Message::Text(ack.to_string())).await.unwrap();
+
+                            if connection == 0 {
+                                let note = json!({
+                                    "jsonrpc": "2.0",
+                                    "method": "eth_subscription",
+                                    "params": { "subscription": "0xsub", "result": sync_log(1001, 0, 20_000_000_000_000_000_000, 36_000_000_000) },
+                                });
+                                ws.send(Message::Text(note.to_string())).await.unwrap();
+                                ws.close(None).await.unwrap();
+                                break;
+                            }
+                        }
+                        "eth_getLogs" => {
+                            // The live log again, a later one in the same block, then block 1003
+                            let logs = json!([
+                                sync_log(1001, 0, 20_000_000_000_000_000_000, 36_000_000_000),
+                                sync_log(1001, 3, 18_000_000_000_000_000_000, 34_200_000_000),
+                                sync_log(1003, 0, 5_000_000_000_000_000_000, 10_000_000_000),
+                            ]);
+                            let reply = json!({ "jsonrpc": "2.0", "id": request["id"], "result": logs });
+                            ws.send(Message::Text(reply.to_string())).await.unwrap();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        });
+
+        (url, requests_rx)
+    }
+
+    #[tokio::test]
+    async fn test_stream_applies_sync_and_catches_up_after_reconnect() {
+        let (url, mut requests) = mock_ws_node().await;
+        let pairs = Arc::new(RwLock::new(vec![weth_usdc_pair()]));
+        let mut stream = PoolStream::new(&url, pairs.clone())
+            .with_reconnect_delay(Duration::from_millis(10));
+
+        let (events_tx, mut events_rx) = mpsc::channel(16);
+        let task = tokio::spawn(async move { stream.run(events_tx).await });
+
+        // Live Sync on the first connection
+        let first = events_rx.recv().await.unwrap();
+        assert_eq!(first.block(), 1001);
+        assert_eq!(pairs.read().await[0].price, dec!(1800));
+
+        // Second connection replays from the last block seen, skipping the
+        // log already applied but not the one after it
+        let second = events_rx.recv().await.unwrap();
+        assert_eq!(second.position(), (1001, 3));
+        let third = events_rx.recv().await.unwrap();
+        assert_eq!(third.block(), 1003);
+        let pair = pairs.read().await[0].clone();
+        assert_eq!(pair.reserve_base, dec!(5));
+        assert_eq!(pair.price, dec!(2000));
+        assert_eq!(pair.reserve_block, Some(1003));
+
+        let mut catch_up = None;
+        while let Ok(request) = requests.try_recv() {
+            if request["method"] == "eth_getLogs" {
+                catch_up = Some(request);
+            }
+        }
+        assert_eq!(catch_up.unwrap()["params"][0]["fromBlock"], json!("0x3e9"));
+
+        drop(events_rx);
+        task.abort();
+    }
+}
@@ -1,20 +1,34 @@
 //! price_feed.rs - Price feed implementations for the arbitrage engine
 //!
-//! Phase 1: Real Metis price feeds from DEX Screener API
-//! Supports Netswap and Tethys DEXes on Metis chain
+//! The PriceFeed trait and its sources: DexScreenerFeed (MetisPriceFeed)
+//! reads pairs for any chain, DEX allowlist and discovery strategy from the
+//! DEX Screener API; OnChainPriceFeed reads UniswapV2 pool reserves over
+//! JSON-RPC, optionally batched through Multicall3; CompositePriceFeed
+//! cross-checks several feeds and withholds pools whose sources disagree;
+//! MockPriceFeed serves fixed pairs for tests.
 
 use async_trait::async_trait;
 use log::{debug, error, info, warn};
-use reqwest::Client;
+use primitive_types::U256;
 use rust_decimal::Decimal;
-use serde::Deserialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
 use tokio::sync::RwLock;
 
-use crate::models::{CachedPrice, Exchange, Token, TradingPair};
+use crate::abi;
+use crate::address::Address;
+use crate::chain::{Chain, METIS_CHAIN_ID};
+use crate::filter::{Exclusion, PairFilter};
+use crate::http_client::{EndpointStats, RateLimitedClient};
+use crate::models::{
+    known_metis_exchanges, CachedPrice, Exchange, MarketStats, PairKey, Pool, PriceKey, Token, TradingPair,
+    TxnCounts, Windowed,
+};
+use crate::multicall::{Call, CallFailure, Multicall};
+use crate::rpc::{BlockTag, RpcClient, METIS_RPC_URL};
+use crate::token_registry::{known_metis_tokens, TokenRegistry, DEFAULT_TOKEN_CACHE_PATH};
 
 /// Trait defining the interface for price feeds
 #[async_trait]
@@ -28,6 +42,35 @@ pub trait PriceFeed: Send + Sync {
     /// Get liquidity for a specific pair
     async fn get_liquidity(&self, base: &str, quote: &str) -> Option<Decimal>;
 
+    /// Get the price quoted by a specific pool
+    async fn get_pool_price(&self, pool_address: Address) -> Option<Decimal> {
+        self.get_trading_pairs().await.into_iter()
+            .find(|p| p.pool.address == pool_address)
+            .map(|p| p.price)
+    }
+
+    /// Get the liquidity of a specific pool
+    async fn get_pool_liquidity(&self, pool_address: Address) -> Option<Decimal> {
+        self.get_trading_pairs().await.into_iter()
+            .find(|p| p.pool.address == pool_address)
+            .map(|p| p.liquidity)
+    }
+
+    /// Get the price for a pair, identified by token addresses, on one
+    /// exchange (the most liquid pool if the exchange has several)
+    async fn get_exchange_price(
+        &self,
+        exchange: &str,
+        base_address: Address,
+        quote_address: Address,
+    ) -> Option<Decimal> {
+        let pair = PairKey::new(base_address, quote_address);
+        self.get_trading_pairs().await.into_iter()
+            .filter(|p| p.exchange().name == exchange && p.pair_key() == pair)
+            .max_by(|a, b| a.liquidity.cmp(&b.liquidity))
+            .and_then(|p| pair.orient(p.canonical_price(), base_address))
+    }
+
     /// Refresh all price data
     async fn refresh(&self) -> anyhow::Result<()>;
 }
@@ -65,15 +108,77 @@ struct DexScreenerPair {
     price_native: Option<String>,
 
     liquidity: Option<LiquidityData>,
+
+    volume: Option<WindowData<f64>>,
+
+    txns: Option<WindowData<TxnData>>,
+
+    #[serde(rename = "priceChange")]
+    price_change: Option<WindowData<f64>>,
+
+    fdv: Option<f64>,
+
+    /// Creation time in milliseconds since the epoch
+    #[serde(rename = "pairCreatedAt")]
+    pair_created_at: Option<i64>,
+}
+
+impl DexScreenerPair {
+    fn market_stats(&self) -> MarketStats {
+        let usd = |w: &Option<WindowData<f64>>| {
+            w.as_ref().map(|w| w.map(|v| v.map(decimal_from_f64).unwrap_or_default())).unwrap_or_default()
+        };
+        let txns = self.txns.as_ref()
+            .map(|w| w.map(|t| t.map(|t| TxnCounts { buys: t.buys, sells: t.sells }).unwrap_or_default()))
+            .unwrap_or_default();
+
+        MarketStats {
+            volume_usd: usd(&self.volume),
+            txns,
+            price_change_percent: usd(&self.price_change),
+            fdv_usd: self.fdv.map(decimal_from_f64),
+            pair_created_at: self.pair_created_at.and_then(chrono::DateTime::from_timestamp_millis),
+        }
+    }
+}
+
+/// DEX Screener's m5/h1/h6/h24 window object; any window may be missing
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct WindowData<T> {
+    m5: Option<T>,
+    h1: Option<T>,
+    h6: Option<T>,
+    h24: Option<T>,
+}
+
+impl<T: Copy> WindowData<T> {
+    fn map<U, F: Fn(Option<T>) -> U>(&self, f: F) -> Windowed<U> {
+        Windowed { m5: f(self.m5), h1: f(self.h1), h6: f(self.h6), h24: f(self.h24) }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct TxnData {
+    buys: u64,
+    sells: u64,
 }
 
 #[derive(Debug, Deserialize)]
 struct TokenData {
     address: String,
-    name: String,
     symbol: String,
 }
 
+/// Comma-separated addresses for DEX Screener's multi-address endpoints
+fn join_addresses(addresses: &[Address]) -> String {
+    addresses.iter().map(Address::to_lowercase_hex).collect::<Vec<_>>().join(",")
+}
+
+/// Convert a JSON float through its shortest decimal representation
+fn decimal_from_f64(v: f64) -> Decimal {
+    Decimal::from_str(&v.to_string()).unwrap_or(Decimal::ZERO)
+}
+
 #[derive(Debug, Deserialize)]
 struct LiquidityData {
     usd: Option<f64>,
@@ -82,109 +187,428 @@ struct LiquidityData {
 }
 
 // ============================================================================
-// MetisPriceFeed - Real price feed for Metis chain
+// DexScreenerFeed - Configurable DEX Screener price feed
 // ============================================================================
 
-/// Real price feed implementation for Metis chain
-/// Fetches data from DEX Screener API for Netswap and Tethys DEXes
+/// Maximum age of a cached price before lookups ignore it
+pub const PRICE_CACHE_MAX_AGE_SECONDS: i64 = 60;
+
+/// Default DEX Screener API root
+pub const DEX_SCREENER_API_URL: &str = "https://api.dexscreener.com/latest/dex";
+
+/// Most pair addresses accepted by one `/pairs/{chainId}/{addresses}` call
+pub const DEX_SCREENER_PAIRS_BATCH_SIZE: usize = 30;
+
+/// Most token addresses accepted by one `/tokens/{addresses}` call
+pub const DEX_SCREENER_TOKENS_BATCH_SIZE: usize = 30;
+
+/// Default number of hops a token crawl expands beyond its seeds
+pub const DEFAULT_CRAWL_MAX_DEPTH: usize = 2;
+
+/// Default cap on pools collected by a token crawl
+pub const DEFAULT_CRAWL_MAX_POOLS: usize = 200;
+
+/// How a DexScreenerFeed finds candidate pairs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DiscoveryStrategy {
+    /// Query `/search?q=<term>` for each term
+    Search { terms: Vec<String> },
+    /// Fetch these pool addresses through `/pairs/{chainId}/{addresses}`;
+    /// watched pools bypass the DEX allowlist
+    Watchlist { pools: Vec<Address> },
+    /// Breadth-first crawl of `/tokens/{addresses}` from seed tokens. Depth 0
+    /// queries the seeds; each further level queries tokens found in the
+    /// previous one. Crawled pools are remembered and refreshed through the
+    /// pairs endpoint until `DexScreenerFeed::recrawl` is called.
+    TokenCrawl {
+        seeds: Vec<Address>,
+        #[serde(default = "default_crawl_max_depth")]
+        max_depth: usize,
+        #[serde(default = "default_crawl_max_pools")]
+        max_pools: usize,
+    },
+}
+
+fn default_crawl_max_depth() -> usize {
+    DEFAULT_CRAWL_MAX_DEPTH
+}
+
+fn default_crawl_max_pools() -> usize {
+    DEFAULT_CRAWL_MAX_POOLS
+}
+
+/// Chain, DEX allowlist and discovery settings for a DexScreenerFeed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DexScreenerConfig {
+    /// DEX Screener chain id (e.g. "metis", "arbitrum")
+    pub chain_id: String,
+    /// EIP-155 chain id recorded on each Exchange and Token (e.g. 1088)
+    #[serde(default = "default_evm_chain_id")]
+    pub evm_chain_id: u64,
+    /// DEX Screener dex ids to keep; empty keeps every DEX on the chain
+    #[serde(default)]
+    pub dex_allowlist: Vec<String>,
+    /// Strategies used to discover pairs, merged in order
+    pub discovery: Vec<DiscoveryStrategy>,
+    /// JSON-RPC endpoint used to resolve token metadata
+    pub rpc_url: String,
+    /// Router, factory and fee tier of known DEXes, matched by dex id
+    #[serde(default)]
+    pub exchanges: Vec<Exchange>,
+    /// Swap fee overrides (bps) for pools whose fee differs from their
+    /// exchange's tier, keyed by pool address
+    #[serde(default)]
+    pub pool_fees: BTreeMap<Address, u32>,
+    #[serde(default = "default_dex_screener_url")]
+    pub base_url: String,
+}
+
+fn default_evm_chain_id() -> u64 {
+    METIS_CHAIN_ID
+}
+
+fn default_dex_screener_url() -> String {
+    DEX_SCREENER_API_URL.to_string()
+}
+
+impl DexScreenerConfig {
+    /// Netswap and Tethys on Metis Andromeda, discovered by search and by
+    /// crawling from METIS, WETH, m.USDC and m.USDT
+    pub fn metis() -> Self {
+        DexScreenerConfig {
+            chain_id: "metis".to_string(),
+            evm_chain_id: METIS_CHAIN_ID,
+            dex_allowlist: vec!["netswap".to_string(), "tethys".to_string()],
+            discovery: vec![
+                DiscoveryStrategy::Search {
+                    terms: vec!["metis".to_string(), "netswap".to_string(), "tethys".to_string()],
+                },
+                DiscoveryStrategy::TokenCrawl {
+                    seeds: known_metis_tokens().into_iter().map(|t| t.address).collect(),
+                    max_depth: DEFAULT_CRAWL_MAX_DEPTH,
+                    max_pools: DEFAULT_CRAWL_MAX_POOLS,
+                },
+            ],
+            rpc_url: METIS_RPC_URL.to_string(),
+            exchanges: known_metis_exchanges(),
+            pool_fees: BTreeMap::new(),
+            base_url: default_dex_screener_url(),
+        }
+    }
+
+    /// Charge `fee_bps` on the pool at `address` instead of its exchange's tier
+    pub fn with_pool_fee(mut self, address: Address, fee_bps: u32) -> Self {
+        self.pool_fees.insert(address, fee_bps);
+        self
+    }
+
+    /// Fee override configured for the pool at `address`
+    pub fn pool_fee_bps(&self, address: Address) -> Option<u32> {
+        self.pool_fees.get(&address).copied()
+    }
+
+    /// Also track these pool addresses regardless of search results
+    pub fn with_watchlist(mut self, pools: Vec<Address>) -> Self {
+        self.discovery.push(DiscoveryStrategy::Watchlist { pools });
+        self
+    }
+
+    /// Also crawl the token graph from `seeds` (e.g. METIS, WETH, USDC, m.USDT)
+    pub fn with_token_crawl(mut self, seeds: Vec<Address>, max_depth: usize, max_pools: usize) -> Self {
+        self.discovery.push(DiscoveryStrategy::TokenCrawl { seeds, max_depth, max_pools });
+        self
+    }
+
+    /// Run against `chain`: record its id on exchanges and tokens and seed
+    /// token crawls with its wrapped native token
+    pub fn with_chain(mut self, chain: &Chain) -> Self {
+        self.evm_chain_id = chain.id;
+        for strategy in &mut self.discovery {
+            if let DiscoveryStrategy::TokenCrawl { seeds, .. } = strategy {
+                if !seeds.contains(&chain.wrapped_native) {
+                    seeds.push(chain.wrapped_native);
+                }
+            }
+        }
+        self
+    }
+
+    /// Whether `dex_id` passes the allowlist
+    pub fn allows_dex(&self, dex_id: &str) -> bool {
+        self.dex_allowlist.is_empty()
+            || self.dex_allowlist.iter().any(|d| d.eq_ignore_ascii_case(dex_id))
+    }
+}
+
+impl Default for DexScreenerConfig {
+    fn default() -> Self {
+        Self::metis()
+    }
+}
+
+/// Price feed backed by the DEX Screener API for one chain and a set of DEXes
 #[derive(Debug, Clone)]
-pub struct MetisPriceFeed {
-    client: Client,
-    dex_screener_url: String,
-    cache: Arc<RwLock<HashMap<String, CachedPrice>>>,
+pub struct DexScreenerFeed {
+    http: RateLimitedClient,
+    config: DexScreenerConfig,
+    tokens: TokenRegistry,
+    cache: Arc<RwLock<HashMap<PriceKey, CachedPrice>>>,
     pairs_cache: Arc<RwLock<Vec<TradingPair>>>,
+    /// Pool addresses found by token crawls, refreshed on every cycle
+    crawled_pools: Arc<RwLock<Vec<Address>>>,
+    filter: PairFilter,
+    excluded: Arc<RwLock<Vec<Exclusion>>>,
 }
 
-impl MetisPriceFeed {
-    /// Create a new MetisPriceFeed instance
+/// DexScreenerFeed preconfigured for Netswap and Tethys on Metis
+pub type MetisPriceFeed = DexScreenerFeed;
+
+impl DexScreenerFeed {
+    /// Create a feed for Netswap and Tethys on Metis
     pub fn new() -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(10))
-            .build()
-            .expect("Failed to create HTTP client");
-
-        MetisPriceFeed {
-            client,
-            dex_screener_url: "https://api.dexscreener.com/latest/dex".to_string(),
+        // Metadata persists across runs; an unusable cache file only costs
+        // the lookups it would have saved
+        let tokens = TokenRegistry::metis_with_cache_file(DEFAULT_TOKEN_CACHE_PATH).unwrap_or_else(|e| {
+            warn!("Token cache {} unusable ({}), resolving in memory", DEFAULT_TOKEN_CACHE_PATH, e);
+            TokenRegistry::metis()
+        });
+        Self::with_config(DexScreenerConfig::metis()).with_token_registry(tokens)
+    }
+
+    /// Create a feed for an arbitrary chain and DEX set
+    pub fn with_config(config: DexScreenerConfig) -> Self {
+        DexScreenerFeed {
+            http: RateLimitedClient::dex_screener(),
+            tokens: TokenRegistry::new(&config.rpc_url).with_chain_id(config.evm_chain_id),
+            config,
             cache: Arc::new(RwLock::new(HashMap::new())),
             pairs_cache: Arc::new(RwLock::new(Vec::new())),
+            crawled_pools: Arc::new(RwLock::new(Vec::new())),
+            filter: PairFilter::default(),
+            excluded: Arc::new(RwLock::new(Vec::new())),
         }
     }
 
-    /// Fetch trading pairs from Metis DEXes (Netswap and Tethys)
-    async fn fetch_metis_pairs(&self) -> Result<Vec<TradingPair>, anyhow::Error> {
+    /// Replace the pair filtering policy
+    pub fn with_filter(mut self, filter: PairFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Pairs dropped by the filter during the last fetch, with reasons
+    pub async fn excluded_pairs(&self) -> Vec<Exclusion> {
+        self.excluded.read().await.clone()
+    }
+
+    /// Use the given registry to resolve token decimals and metadata,
+    /// looked up on this feed's chain
+    pub fn with_token_registry(mut self, tokens: TokenRegistry) -> Self {
+        self.tokens = tokens.with_chain_id(self.config.evm_chain_id);
+        self
+    }
+
+    /// Pool addresses found by token crawls so far
+    pub async fn crawled_pools(&self) -> Vec<Address> {
+        self.crawled_pools.read().await.clone()
+    }
+
+    /// Forget crawled pools so the next refresh crawls the token graph again
+    pub async fn recrawl(&self) {
+        self.crawled_pools.write().await.clear();
+    }
+
+    /// Use a custom HTTP client (rate limits, retry policy)
+    pub fn with_http_client(mut self, http: RateLimitedClient) -> Self {
+        self.http = http;
+        self
+    }
+
+    pub fn config(&self) -> &DexScreenerConfig {
+        &self.config
+    }
+
+    /// Request counters per DEX Screener endpoint
+    pub async fn endpoint_stats(&self) -> HashMap<String, EndpointStats> {
+        self.http.stats().await
+    }
+
+    /// Run every discovery strategy and keep allowlisted pairs on the chain
+    async fn fetch_pairs(&self) -> Result<Vec<TradingPair>, anyhow::Error> {
         let mut all_pairs = Vec::new();
+        let mut seen = HashSet::new();
+
+        for strategy in &self.config.discovery {
+            let (found, watched) = match strategy {
+                DiscoveryStrategy::Search { terms } => (self.discover_by_search(terms).await, false),
+                DiscoveryStrategy::Watchlist { pools } => (self.discover_by_address(pools).await, true),
+                DiscoveryStrategy::TokenCrawl { seeds, max_depth, max_pools } => {
+                    (self.discover_by_crawl(seeds, *max_depth, *max_pools).await, false)
+                }
+            };
 
-        // Use search endpoint to find Metis chain pairs
-        // Search for common Metis tokens to get pairs
-        let search_terms = vec!["metis", "netswap", "tethys"];
+            for pair_data in found {
+                if pair_data.chain_id != self.config.chain_id {
+                    continue;
+                }
+                if !watched && !self.config.allows_dex(&pair_data.dex_id) {
+                    continue;
+                }
 
-        for term in search_terms {
-            let url = format!("{}/search?q={}", self.dex_screener_url, term);
+                match self.convert_to_trading_pair(pair_data).await {
+                    Ok(pair) => {
+                        // The same pool may come from several strategies; keep the first
+                        if seen.insert(PriceKey::from_pair(&pair)) {
+                            all_pairs.push(pair);
+                        }
+                    }
+                    Err(e) => {
+                        // Don't log every conversion failure, too noisy
+                        debug!("Skipped pair conversion: {}", e);
+                    }
+                }
+            }
+        }
+
+        self.tokens.flush().await;
+
+        let outcome = self.filter.apply(all_pairs);
+        outcome.log("DEX Screener");
+        *self.excluded.write().await = outcome.excluded;
+
+        info!("Total {} pairs fetched: {}", self.config.chain_id, outcome.kept.len());
+        Ok(outcome.kept)
+    }
+
+    /// Raw pairs returned by the search endpoint for each term
+    async fn discover_by_search(&self, terms: &[String]) -> Vec<DexScreenerPair> {
+        let mut found = Vec::new();
+
+        for term in terms {
+            let url = format!("{}/search?q={}", self.config.base_url, term);
 
             debug!("Fetching from: {}", url);
 
-            let response = match self.client.get(&url).send().await {
-                Ok(resp) => resp,
+            // Retries and rate limiting happen inside the client; a failure
+            // here is already counted in endpoint_stats()
+            let data: DexScreenerResponse = match self.http.get_json("search", &url).await {
+                Ok(d) => d,
                 Err(e) => {
                     warn!("Failed to fetch for term '{}': {}", term, e);
                     continue;
                 }
             };
 
-            if !response.status().is_success() {
-                warn!("DEX Screener returned status {} for '{}'", response.status(), term);
-                continue;
-            }
+            let pairs = data.pairs.unwrap_or_default();
+            debug!("Found {} pairs for search term '{}'", pairs.len(), term);
+            found.extend(pairs);
+        }
 
-            let data: DexScreenerResponse = match response.json().await {
+        found
+    }
+
+    /// Raw pairs for explicit pool addresses, batched to the endpoint limit
+    async fn discover_by_address(&self, pools: &[Address]) -> Vec<DexScreenerPair> {
+        let mut found = Vec::new();
+
+        for batch in pools.chunks(DEX_SCREENER_PAIRS_BATCH_SIZE) {
+            let url = format!("{}/pairs/{}/{}", self.config.base_url, self.config.chain_id, join_addresses(batch));
+
+            debug!("Fetching {} watched pools from: {}", batch.len(), url);
+
+            let data: DexScreenerResponse = match self.http.get_json("pairs", &url).await {
                 Ok(d) => d,
                 Err(e) => {
-                    warn!("Failed to parse response for '{}': {}", term, e);
+                    warn!("Failed to fetch {} watched pools: {}", batch.len(), e);
                     continue;
                 }
             };
 
             let pairs = data.pairs.unwrap_or_default();
-            debug!("Found {} pairs for search term '{}'", pairs.len(), term);
+            if pairs.len() < batch.len() {
+                warn!("DEX Screener returned {} of {} watched pools", pairs.len(), batch.len());
+            }
+            found.extend(pairs);
+        }
 
-            // Filter for Metis chain only (chainId == "metis") and supported DEXes
-            for pair_data in pairs {
-                // Only include Metis chain pairs from Netswap or Tethys
-                if pair_data.chain_id != "metis" {
-                    continue;
-                }
-                if pair_data.dex_id != "netswap" && pair_data.dex_id != "tethys" {
-                    continue;
-                }
+        found
+    }
 
-                match self.convert_to_trading_pair(pair_data) {
-                    Ok(pair) => {
-                        // Avoid duplicates
-                        if !all_pairs.iter().any(|p: &TradingPair| p.full_id() == pair.full_id()) {
-                            all_pairs.push(pair);
+    /// Pools reachable from `seeds` in the token graph, crawled once and then
+    /// refreshed by address
+    async fn discover_by_crawl(&self, seeds: &[Address], max_depth: usize, max_pools: usize) -> Vec<DexScreenerPair> {
+        let known = self.crawled_pools.read().await.clone();
+        if !known.is_empty() {
+            return self.discover_by_address(&known).await;
+        }
+
+        let mut visited: HashSet<Address> = HashSet::new();
+        let mut frontier: Vec<Address> = seeds.iter().copied().filter(|seed| visited.insert(*seed)).collect();
+        let mut pool_addresses = HashSet::new();
+        let mut found = Vec::new();
+
+        'crawl: for depth in 0..=max_depth {
+            if frontier.is_empty() {
+                break;
+            }
+            debug!("Token crawl depth {}: {} tokens", depth, frontier.len());
+            let mut next = Vec::new();
+
+            for batch in frontier.chunks(DEX_SCREENER_TOKENS_BATCH_SIZE) {
+                let url = format!("{}/tokens/{}", self.config.base_url, join_addresses(batch));
+
+                let data: DexScreenerResponse = match self.http.get_json("tokens", &url).await {
+                    Ok(d) => d,
+                    Err(e) => {
+                        warn!("Token crawl failed for {} tokens: {}", batch.len(), e);
+                        continue;
+                    }
+                };
+
+                // The tokens endpoint spans all chains; keep allowlisted pools on ours
+                for pair in data.pairs.unwrap_or_default() {
+                    if pair.chain_id != self.config.chain_id || !self.config.allows_dex(&pair.dex_id) {
+                        continue;
+                    }
+                    let Ok(pool) = pair.pair_address.parse::<Address>() else { continue };
+                    if !pool_addresses.insert(pool) {
+                        continue;
+                    }
+
+                    for token in [&pair.base_token.address, &pair.quote_token.address] {
+                        if let Ok(token) = token.parse::<Address>() {
+                            if visited.insert(token) {
+                                next.push(token);
+                            }
                         }
                     }
-                    Err(e) => {
-                        // Don't log every conversion failure, too noisy
-                        debug!("Skipped pair conversion: {}", e);
+                    found.push((pool, pair));
+
+                    if found.len() >= max_pools {
+                        break 'crawl;
                     }
                 }
             }
+
+            frontier = next;
         }
 
-        info!("Total pairs fetched: {}", all_pairs.len());
-        Ok(all_pairs)
+        info!("Token crawl found {} pools from {} seeds", found.len(), seeds.len());
+        let (pools, found): (Vec<Address>, Vec<DexScreenerPair>) = found.into_iter().unzip();
+        *self.crawled_pools.write().await = pools;
+        found
     }
 
     /// Convert DEX Screener pair data to our TradingPair model
-    fn convert_to_trading_pair(
+    async fn convert_to_trading_pair(
         &self,
         data: DexScreenerPair,
     ) -> Result<TradingPair, anyhow::Error> {
-        // Extract price - use priceUsd if available, otherwise priceNative
-        let price_str = data.price_usd
-            .or(data.price_native)
+        let market = data.market_stats();
+
+        // priceNative is quote per base, the unit pairs are compared in;
+        // priceUsd is the base token's USD price and only informational
+        let price_str = data.price_native
             .ok_or_else(|| anyhow::anyhow!("No price data"))?;
 
         let price = Decimal::from_str(&price_str)
@@ -194,71 +618,128 @@ impl MetisPriceFeed {
         if price <= Decimal::ZERO {
             return Err(anyhow::anyhow!("Invalid price: {}", price_str));
         }
+        let price_usd = data.price_usd.as_deref()
+            .and_then(|p| Decimal::from_str(p).ok())
+            .filter(|p| *p > Decimal::ZERO);
 
         // Extract liquidity data
         let liquidity = data.liquidity.as_ref();
         let liquidity_usd = liquidity
             .and_then(|l| l.usd)
-            .map(|v| Decimal::from_str(&v.to_string()).unwrap_or(Decimal::ZERO))
+            .map(decimal_from_f64)
             .unwrap_or(Decimal::ZERO);
 
-        // Skip pairs with very low liquidity (< $1000)
-        if liquidity_usd < Decimal::from(1000) {
-            return Err(anyhow::anyhow!("Liquidity too low: ${}", liquidity_usd));
-        }
-
         let reserve_base = liquidity
             .and_then(|l| l.base)
-            .map(|v| Decimal::from_str(&v.to_string()).unwrap_or(Decimal::ZERO))
+            .map(decimal_from_f64)
             .unwrap_or(Decimal::ZERO);
 
         let reserve_quote = liquidity
             .and_then(|l| l.quote)
-            .map(|v| Decimal::from_str(&v.to_string()).unwrap_or(Decimal::ZERO))
+            .map(decimal_from_f64)
             .unwrap_or(Decimal::ZERO);
 
-        // Create token models
-        let base_token = Token::new(
-            &data.base_token.symbol,
-            &data.base_token.name,
-            18, // Default to 18 decimals, will be refined in future phases
-            &data.base_token.address,
-        );
-
-        let quote_token = Token::new(
-            &data.quote_token.symbol,
-            &data.quote_token.name,
-            18,
-            &data.quote_token.address,
-        );
-
-        // Create exchange model
-        let exchange = Exchange::new(
-            &data.dex_id,
-            "Metis",
-            &data.pair_address,
-        );
+        // Resolve token models (real decimals) through the registry
+        let pool_address: Address = data.pair_address.parse()?;
+        let base_token = self.tokens.resolve(data.base_token.address.parse()?).await
+            .map_err(|e| anyhow::anyhow!("Unresolved token {}: {}", data.base_token.symbol, e))?;
+        let quote_token = self.tokens.resolve(data.quote_token.address.parse()?).await
+            .map_err(|e| anyhow::anyhow!("Unresolved token {}: {}", data.quote_token.symbol, e))?;
+
+        // Create exchange and pool models
+        let exchange = Exchange::from_known(&self.config.exchanges, &data.dex_id, self.config.evm_chain_id);
+        let mut pool = Pool::new(pool_address, exchange, base_token.clone(), quote_token.clone());
+        if let Some(fee_bps) = self.config.pool_fee_bps(pool_address) {
+            pool = pool.with_fee_bps(fee_bps);
+        }
 
-        Ok(TradingPair::new(
+        let pair = TradingPair::new(
             base_token,
             quote_token,
-            exchange,
+            pool,
             price,
             liquidity_usd,
             reserve_base,
             reserve_quote,
-        ))
+        )
+        .with_market_stats(market);
+        Ok(match price_usd {
+            Some(price_usd) => pair.with_price_usd(price_usd),
+            None => pair,
+        })
     }
 }
 
-impl Default for MetisPriceFeed {
+impl DexScreenerFeed {
+    /// Replace the pair set and record one price cache entry per pool,
+    /// priced token1 per token0 whichever way round the pool was listed
+    async fn store_pairs(&self, pairs: Vec<TradingPair>) {
+        let mut price_cache = self.cache.write().await;
+        let mut pairs_cache = self.pairs_cache.write().await;
+
+        let now = chrono::Utc::now();
+        for pair in &pairs {
+            price_cache.insert(PriceKey::from_pair(pair), CachedPrice {
+                price: pair.canonical_price(),
+                liquidity: pair.liquidity,
+                timestamp: now,
+                source: format!("DEX Screener - {}", pair.exchange().name),
+            });
+        }
+        *pairs_cache = pairs;
+
+        info!("Price feed refreshed: {} entries cached", price_cache.len());
+    }
+
+    /// Fresh cache entries matching `filter`, most liquid first
+    async fn fresh_entries<F>(&self, filter: F) -> Vec<(PriceKey, CachedPrice)>
+    where
+        F: Fn(&PriceKey) -> bool,
+    {
+        let cache = self.cache.read().await;
+        let mut entries: Vec<(PriceKey, CachedPrice)> = cache.iter()
+            .filter(|(key, cached)| filter(key) && !cached.is_stale(PRICE_CACHE_MAX_AGE_SECONDS))
+            .map(|(key, cached)| (key.clone(), cached.clone()))
+            .collect();
+        entries.sort_by_key(|(_, c)| std::cmp::Reverse(c.liquidity));
+        entries
+    }
+
+    /// Cache keys of all pools trading `base`/`quote` by symbol, listed
+    /// either way round, with the address of the `base` token in each
+    async fn keys_for_symbols(&self, base: &str, quote: &str) -> Vec<(PriceKey, Address)> {
+        self.pairs_cache.read().await.iter()
+            .filter_map(|p| {
+                let (b, q) = (&p.base_token, &p.quote_token);
+                if b.symbol == base && q.symbol == quote {
+                    Some((PriceKey::from_pair(p), b.address))
+                } else if q.symbol == base && b.symbol == quote {
+                    Some((PriceKey::from_pair(p), q.address))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Price in `quote` of the `base` symbol, and the liquidity behind it,
+    /// from the most liquid fresh pool
+    async fn symbol_entry(&self, base: &str, quote: &str) -> Option<(Decimal, Decimal)> {
+        let keys = self.keys_for_symbols(base, quote).await;
+        let (key, cached) = self.fresh_entries(|k| keys.iter().any(|(key, _)| key == k)).await.into_iter().next()?;
+        let (_, base_address) = keys.iter().find(|(k, _)| *k == key)?;
+        Some((key.pair.orient(cached.price, *base_address)?, cached.liquidity))
+    }
+}
+
+impl Default for DexScreenerFeed {
     fn default() -> Self {
         Self::new()
     }
 }
 
 #[async_trait]
-impl PriceFeed for MetisPriceFeed {
+impl PriceFeed for DexScreenerFeed {
     async fn get_trading_pairs(&self) -> Vec<TradingPair> {
         // Check cache first
         {
@@ -269,7 +750,7 @@ impl PriceFeed for MetisPriceFeed {
         }
 
         // Fetch fresh data
-        match self.fetch_metis_pairs().await {
+        match self.fetch_pairs().await {
             Ok(pairs) => {
                 // Update cache
                 let mut cache = self.pairs_cache.write().await;
@@ -277,72 +758,623 @@ impl PriceFeed for MetisPriceFeed {
                 pairs
             }
             Err(e) => {
-                error!("Failed to fetch Metis pairs: {}", e);
+                error!("Failed to fetch {} pairs: {}", self.config.chain_id, e);
                 Vec::new()
             }
         }
     }
 
+    /// Price of the most liquid fresh pool trading `base`/`quote`
     async fn get_price(&self, base: &str, quote: &str) -> Option<Decimal> {
-        let cache = self.cache.read().await;
-        let key = format!("{}/{}", base, quote);
+        // Price not in cache (or stale) returns None (caller should refresh)
+        self.symbol_entry(base, quote).await.map(|(price, _)| price)
+    }
+
+    /// Liquidity of the most liquid fresh pool trading `base`/`quote`
+    async fn get_liquidity(&self, base: &str, quote: &str) -> Option<Decimal> {
+        self.symbol_entry(base, quote).await.map(|(_, liquidity)| liquidity)
+    }
+
+    /// Price of the pool's base token as listed (token0 if not known)
+    async fn get_pool_price(&self, pool_address: Address) -> Option<Decimal> {
+        let listed_base = self.pairs_cache.read().await.iter()
+            .find(|p| p.pool.address == pool_address)
+            .map(|p| p.base_token.address);
+        let (key, cached) = self.fresh_entries(|k| k.pool_address == pool_address).await.into_iter().next()?;
+        key.pair.orient(cached.price, listed_base.unwrap_or(key.pair.token0))
+    }
+
+    async fn get_pool_liquidity(&self, pool_address: Address) -> Option<Decimal> {
+        self.fresh_entries(|k| k.pool_address == pool_address).await.first().map(|(_, c)| c.liquidity)
+    }
+
+    async fn get_exchange_price(
+        &self,
+        exchange: &str,
+        base_address: Address,
+        quote_address: Address,
+    ) -> Option<Decimal> {
+        let pair = PairKey::new(base_address, quote_address);
+        let (_, cached) = self.fresh_entries(|k| k.exchange == exchange && k.pair == pair).await.into_iter().next()?;
+        pair.orient(cached.price, base_address)
+    }
+
+    async fn refresh(&self) -> anyhow::Result<()> {
+        debug!("Refreshing {} price feed...", self.config.chain_id);
+
+        let pairs = self.fetch_pairs().await?;
+        self.store_pairs(pairs).await;
+        Ok(())
+    }
+}
+
+// ============================================================================
+// OnChainPriceFeed - Reserves read directly from pool contracts
+// ============================================================================
 
-        if let Some(cached) = cache.get(&key) {
-            if !cached.is_stale(60) {
-                // 60 second cache
-                return Some(cached.price);
+/// A UniswapV2-style pool tracked by the on-chain feed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolConfig {
+    /// Pool (pair) contract address
+    pub address: Address,
+    /// Exchange the pool belongs to (e.g. "netswap", "tethys")
+    pub exchange: String,
+    /// Swap fee (bps) if it differs from the exchange's tier
+    #[serde(default)]
+    pub fee_bps: Option<u32>,
+}
+
+impl PoolConfig {
+    pub fn new(address: Address, exchange: &str) -> Self {
+        PoolConfig {
+            address,
+            exchange: exchange.to_string(),
+            fee_bps: None,
+        }
+    }
+
+    pub fn with_fee_bps(mut self, fee_bps: u32) -> Self {
+        self.fee_bps = Some(fee_bps);
+        self
+    }
+}
+
+/// Price feed reading `getReserves()`, `token0()` and `token1()` straight
+/// from UniswapV2-style pool contracts over JSON-RPC
+///
+/// All reads for a refresh are pinned to a single block, so reserves are
+/// exact and consistent with `TradingPair::reserve_block`. Pairs are
+/// oriented as token0/token1; `liquidity` is expressed in quote-token units
+/// (twice the quote reserve) since no USD price is available on-chain.
+#[derive(Debug, Clone)]
+pub struct OnChainPriceFeed {
+    rpc: RpcClient,
+    tokens: TokenRegistry,
+    multicall: Option<Multicall>,
+    chain_id: u64,
+    exchanges: Vec<Exchange>,
+    pools: Vec<PoolConfig>,
+    pairs_cache: Arc<RwLock<Vec<TradingPair>>>,
+    filter: PairFilter,
+}
+
+impl OnChainPriceFeed {
+    /// Create a feed for the given pools using the given JSON-RPC endpoint
+    pub fn new(rpc_url: &str, pools: Vec<PoolConfig>) -> Self {
+        OnChainPriceFeed {
+            rpc: RpcClient::new(rpc_url),
+            tokens: TokenRegistry::new(rpc_url),
+            multicall: None,
+            chain_id: METIS_CHAIN_ID,
+            exchanges: known_metis_exchanges(),
+            pools,
+            pairs_cache: Arc::new(RwLock::new(Vec::new())),
+            // Liquidity here is in quote units, not USD, so nothing is
+            // dropped unless a filter is configured for this feed
+            filter: PairFilter::permissive(),
+        }
+    }
+
+    /// Replace the pair filtering policy
+    pub fn with_filter(mut self, filter: PairFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Chain the RPC endpoint serves (Metis Andromeda by default)
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = chain_id;
+        self.tokens = self.tokens.with_chain_id(chain_id);
+        self
+    }
+
+    /// Run against `chain`, batching reads through its Multicall3 when it
+    /// has one
+    pub fn with_chain(mut self, chain: &Chain) -> Self {
+        self.multicall = Multicall::for_chain(self.rpc.clone(), chain);
+        self.with_chain_id(chain.id)
+    }
+
+    /// Router and factory of the exchanges pools are configured with
+    pub fn with_exchanges(mut self, exchanges: Vec<Exchange>) -> Self {
+        self.exchanges = exchanges;
+        self
+    }
+
+    /// Create a feed against the public Metis Andromeda RPC
+    pub fn metis(pools: Vec<PoolConfig>) -> Self {
+        Self::new(METIS_RPC_URL, pools)
+    }
+
+    /// Use the given registry to resolve token decimals and metadata,
+    /// looked up on this feed's chain
+    pub fn with_token_registry(mut self, tokens: TokenRegistry) -> Self {
+        self.tokens = tokens.with_chain_id(self.chain_id);
+        self
+    }
+
+    /// Pools tracked by this feed
+    pub fn pools(&self) -> &[PoolConfig] {
+        &self.pools
+    }
+
+    /// Shared handle to the cached pairs, e.g. for a `PoolStream` to update
+    pub fn pairs_handle(&self) -> Arc<RwLock<Vec<TradingPair>>> {
+        self.pairs_cache.clone()
+    }
+
+    /// Batch pool reads through Multicall3 instead of one call per field
+    pub fn with_multicall(mut self, multicall: Multicall) -> Self {
+        self.multicall = Some(multicall);
+        self
+    }
+
+    /// Read all tracked pools at the latest block
+    async fn fetch_pool_pairs(&self) -> anyhow::Result<Vec<TradingPair>> {
+        let block = self.rpc.block_number().await?;
+
+        let states = match &self.multicall {
+            Some(multicall) => self.read_pool_states_batched(multicall, block).await,
+            None => {
+                let mut states = Vec::with_capacity(self.pools.len());
+                for pool in &self.pools {
+                    states.push(self.read_pool_state(pool, block).await);
+                }
+                states
+            }
+        };
+
+        // Resolve every token up front so a registry with Multicall3 can batch them
+        let addresses: Vec<Address> = states.iter()
+            .filter_map(|s| s.as_ref().ok())
+            .flat_map(|s| [s.token0, s.token1])
+            .collect();
+        self.tokens.resolve_many(&addresses).await;
+
+        let mut pairs = Vec::with_capacity(self.pools.len());
+        for (pool, state) in self.pools.iter().zip(states) {
+            let pair = match state {
+                Ok(state) => self.build_pair(pool, state, block).await,
+                Err(e) => Err(e),
+            };
+            match pair {
+                Ok(pair) => pairs.push(pair),
+                Err(e) => warn!("Failed to read pool {} ({}): {}", pool.address, pool.exchange, e),
             }
         }
+        self.tokens.flush().await;
+
+        if pairs.is_empty() && !self.pools.is_empty() {
+            return Err(anyhow::anyhow!("No pools could be read at block {}", block));
+        }
+
+        let outcome = self.filter.apply(pairs);
+        outcome.log("On-chain");
 
-        // Price not in cache, return None (caller should refresh)
-        None
+        info!("Read {} pools on-chain at block {}", outcome.kept.len(), block);
+        Ok(outcome.kept)
     }
 
-    async fn get_liquidity(&self, base: &str, quote: &str) -> Option<Decimal> {
-        let cache = self.cache.read().await;
-        let key = format!("{}/{}-liq", base, quote);
+    /// Read a single pool's tokens and reserves at `block`
+    async fn read_pool_state(&self, pool: &PoolConfig, block: u64) -> anyhow::Result<PoolState> {
+        let at = BlockTag::Number(block);
+        let target = pool.address.to_lowercase_hex();
+
+        let token0 = self.rpc.eth_call(&target, &abi::SELECTOR_TOKEN0, at).await?;
+        let token1 = self.rpc.eth_call(&target, &abi::SELECTOR_TOKEN1, at).await?;
+        let reserves = self.rpc.eth_call(&target, &abi::SELECTOR_GET_RESERVES, at).await?;
 
-        cache.get(&key).map(|c| c.price)
+        PoolState::decode(&token0, &token1, &reserves)
+    }
+
+    /// Read every pool's tokens and reserves at `block` through Multicall3
+    async fn read_pool_states_batched(
+        &self,
+        multicall: &Multicall,
+        block: u64,
+    ) -> Vec<anyhow::Result<PoolState>> {
+        const FIELDS: [[u8; 4]; 3] = [
+            abi::SELECTOR_TOKEN0,
+            abi::SELECTOR_TOKEN1,
+            abi::SELECTOR_GET_RESERVES,
+        ];
+
+        let calls = self.pools.iter()
+            .enumerate()
+            .flat_map(|(i, pool)| {
+                FIELDS.iter().enumerate().map(move |(field, selector)| {
+                    ((i, field), Call::new(pool.address, *selector))
+                })
+            })
+            .collect();
+
+        let mut results = multicall.aggregate(calls, BlockTag::Number(block)).await;
+
+        (0..self.pools.len())
+            .map(|i| {
+                let mut field = |f: usize| {
+                    results.remove(&(i, f))
+                        .unwrap_or_else(|| Err(CallFailure::Batch("missing result".to_string())))
+                };
+                let (token0, token1, reserves) = (field(0)?, field(1)?, field(2)?);
+                PoolState::decode(&token0, &token1, &reserves)
+            })
+            .collect()
+    }
+
+    /// Turn raw pool state into a TradingPair oriented token0/token1
+    async fn build_pair(
+        &self,
+        pool: &PoolConfig,
+        state: PoolState,
+        block: u64,
+    ) -> anyhow::Result<TradingPair> {
+        let base_token = self.tokens.resolve(state.token0).await?;
+        let quote_token = self.tokens.resolve(state.token1).await?;
+
+        let raw_base = base_token.amount(state.reserve0);
+        let raw_quote = quote_token.amount(state.reserve1);
+        if raw_base.is_zero() {
+            return Err(anyhow::anyhow!("Empty reserves"));
+        }
+        // Display values are rounded to fit a Decimal, while raw_reserves
+        // keep the exact amounts; only dust or rugged pools, whose price
+        // exceeds a Decimal outright, are rejected
+        let (Some(reserve_base), Some(reserve_quote), Some(price)) =
+            (raw_base.to_decimal_lossy(), raw_quote.to_decimal_lossy(), raw_quote.ratio_lossy(&raw_base))
+        else {
+            return Err(anyhow::anyhow!("Price overflows ({} / {})", raw_quote, raw_base));
+        };
+        let liquidity = reserve_quote.saturating_mul(Decimal::from(2));
+
+        let exchange = Exchange::from_known(&self.exchanges, &pool.exchange, self.chain_id);
+        let fee_bps = pool.fee_bps.unwrap_or(exchange.fee_bps);
+        let pool = Pool::new(pool.address, exchange, base_token.clone(), quote_token.clone()).with_fee_bps(fee_bps);
+
+        Ok(TradingPair::new(
+            base_token,
+            quote_token,
+            pool,
+            price,
+            liquidity,
+            reserve_base,
+            reserve_quote,
+        )
+        .with_reserve_block(block)
+        .with_raw_reserves(raw_base, raw_quote))
+    }
+}
+
+/// Raw on-chain state of a UniswapV2-style pool
+#[derive(Debug, Clone)]
+struct PoolState {
+    token0: Address,
+    token1: Address,
+    reserve0: U256,
+    reserve1: U256,
+}
+
+impl PoolState {
+    /// Decode the return data of token0(), token1() and getReserves()
+    fn decode(token0: &[u8], token1: &[u8], reserves: &[u8]) -> anyhow::Result<Self> {
+        Ok(PoolState {
+            token0: abi::decode_address(abi::word(token0, 0)?)?.parse()?,
+            token1: abi::decode_address(abi::word(token1, 0)?)?.parse()?,
+            reserve0: abi::decode_u256(abi::word(reserves, 0)?),
+            reserve1: abi::decode_u256(abi::word(reserves, 1)?),
+        })
+    }
+}
+
+#[async_trait]
+impl PriceFeed for OnChainPriceFeed {
+    async fn get_trading_pairs(&self) -> Vec<TradingPair> {
+        {
+            let cache = self.pairs_cache.read().await;
+            if !cache.is_empty() {
+                return cache.clone();
+            }
+        }
+
+        match self.fetch_pool_pairs().await {
+            Ok(pairs) => {
+                let mut cache = self.pairs_cache.write().await;
+                *cache = pairs.clone();
+                pairs
+            }
+            Err(e) => {
+                error!("Failed to read on-chain pools: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    async fn get_price(&self, base: &str, quote: &str) -> Option<Decimal> {
+        self.pairs_cache.read().await.iter()
+            .find(|p| p.base_token.symbol == base && p.quote_token.symbol == quote)
+            .map(|p| p.price)
+    }
+
+    async fn get_liquidity(&self, base: &str, quote: &str) -> Option<Decimal> {
+        self.pairs_cache.read().await.iter()
+            .find(|p| p.base_token.symbol == base && p.quote_token.symbol == quote)
+            .map(|p| p.liquidity)
     }
 
     async fn refresh(&self) -> anyhow::Result<()> {
-        debug!("Refreshing Metis price feed...");
+        debug!("Refreshing on-chain price feed...");
 
-        let pairs = self.fetch_metis_pairs().await?;
-        let mut price_cache = self.cache.write().await;
-        let mut pairs_cache = self.pairs_cache.write().await;
+        let pairs = self.fetch_pool_pairs().await?;
+        *self.pairs_cache.write().await = pairs;
+        Ok(())
+    }
+}
+
+// ============================================================================
+// CompositePriceFeed - Cross-checks several feeds against each other
+// ============================================================================
 
-        // Update pairs cache
-        *pairs_cache = pairs.clone();
+/// Default tolerance before a source counts as divergent (1%)
+pub const DEFAULT_DIVERGENCE_TOLERANCE_BPS: u32 = 100;
+
+/// A source whose price for a pool strayed from the median
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub pool: Address,
+    pub pair_id: String,
+    pub source: String,
+    pub price: Decimal,
+    pub median: Decimal,
+    pub deviation_bps: Decimal,
+}
 
-        // Update price cache
-        for pair in pairs {
-            // Cache price
-            let price_key = format!("{}/{}",
-                pair.base_token.symbol,
-                pair.quote_token.symbol
-            );
+#[derive(Debug, Default)]
+struct CompositeState {
+    trusted: Vec<TradingPair>,
+    untrusted: Vec<TradingPair>,
+    divergences: Vec<Divergence>,
+}
 
-            price_cache.insert(price_key, CachedPrice {
-                price: pair.price,
-                timestamp: chrono::Utc::now(),
-                source: format!("DEX Screener - {}", pair.exchange.name),
-            });
+/// Price feed merging several sources by pool address
+///
+/// Each pool's price is the median across the sources that report it. A
+/// pool is trusted when at least `quorum` sources agree with the median
+/// within `tolerance_bps`; untrusted pools are withheld from
+/// `get_trading_pairs` so detection skips them. Prices are compared as
+/// quote per base, the unit every feed's `TradingPair::price` carries
+/// (DEX Screener's `priceNative`, on-chain reserve ratios), whichever way
+/// round each source lists the pool. Sources are listed in priority order:
+/// the highest-priority source's pair (tokens, reserves) is kept, with the
+/// median price. Liquidity is taken from the highest-priority source that
+/// reports it in USD, so quote-unit liquidity (on-chain reserves) is never
+/// judged against a USD minimum.
+pub struct CompositePriceFeed {
+    sources: Vec<(String, Arc<dyn PriceFeed>)>,
+    quote_liquidity_sources: HashSet<String>,
+    quorum: usize,
+    tolerance_bps: u32,
+    filter: PairFilter,
+    state: RwLock<CompositeState>,
+}
 
-            // Cache liquidity
-            let liq_key = format!("{}/{}-liq",
-                pair.base_token.symbol,
-                pair.quote_token.symbol
-            );
+impl CompositePriceFeed {
+    /// Create a composite over named sources (highest priority first)
+    pub fn new(sources: Vec<(String, Arc<dyn PriceFeed>)>) -> Self {
+        CompositePriceFeed {
+            sources,
+            quote_liquidity_sources: HashSet::new(),
+            quorum: 1,
+            tolerance_bps: DEFAULT_DIVERGENCE_TOLERANCE_BPS,
+            // Sources apply their own filters; this one narrows the merge
+            filter: PairFilter::permissive(),
+            state: RwLock::new(CompositeState::default()),
+        }
+    }
 
-            price_cache.insert(liq_key, CachedPrice {
-                price: pair.liquidity,
-                timestamp: chrono::Utc::now(),
-                source: format!("DEX Screener - {}", pair.exchange.name),
-            });
+    /// Filter applied to trusted pools after merging
+    pub fn with_filter(mut self, filter: PairFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Mark `source` as reporting liquidity in quote units rather than USD.
+    /// Its liquidity is not used, and pools no USD source reports are
+    /// withheld.
+    pub fn with_quote_liquidity_source(mut self, source: &str) -> Self {
+        self.quote_liquidity_sources.insert(source.to_string());
+        self
+    }
+
+    /// Minimum number of agreeing sources for a pool to be trusted
+    pub fn with_quorum(mut self, quorum: usize) -> Self {
+        self.quorum = quorum.max(1);
+        self
+    }
+
+    /// Maximum deviation from the median, in basis points
+    pub fn with_tolerance_bps(mut self, tolerance_bps: u32) -> Self {
+        self.tolerance_bps = tolerance_bps;
+        self
+    }
+
+    /// Pools withheld because their sources disagreed or lacked quorum
+    pub async fn untrusted_pairs(&self) -> Vec<TradingPair> {
+        self.state.read().await.untrusted.clone()
+    }
+
+    /// Sources that diverged from the median during the last refresh
+    pub async fn divergences(&self) -> Vec<Divergence> {
+        self.state.read().await.divergences.clone()
+    }
+
+    /// Gather every source's pairs and merge them
+    async fn merge(&self) -> CompositeState {
+        // pool address -> (source name, pair) in source priority order;
+        // pools are merged in the order they were first seen
+        let mut by_pool: HashMap<Address, Vec<(String, TradingPair)>> = HashMap::new();
+        let mut pools: Vec<Address> = Vec::new();
+
+        for (name, source) in &self.sources {
+            for pair in source.get_trading_pairs().await {
+                let pool = pair.pool.address;
+                let entries = by_pool.entry(pool).or_default();
+                if entries.is_empty() {
+                    pools.push(pool);
+                }
+                entries.push((name.clone(), pair));
+            }
         }
 
-        info!("Price feed refreshed: {} entries cached", price_cache.len());
+        let mut state = CompositeState::default();
+        let tolerance = Decimal::from(self.tolerance_bps);
+
+        for pool in pools {
+            let entries = by_pool.remove(&pool).unwrap_or_default();
+            let reference = entries[0].1.clone();
+            let key = reference.pair_key();
+
+            // Every source's price is quote per base; normalize to token1
+            // per token0 and back into the reference orientation, so
+            // sources listing the pool either way round compare directly
+            let prices: Vec<(String, Decimal)> = entries.iter()
+                .filter(|(_, pair)| pair.pair_key() == key)
+                .filter_map(|(name, pair)| {
+                    let price = key.orient(pair.canonical_price(), reference.base_token.address)?;
+                    (price > Decimal::ZERO).then(|| (name.clone(), price))
+                })
+                .collect();
+
+            let Some(median) = median(prices.iter().map(|(_, p)| *p).collect()) else {
+                state.untrusted.push(reference);
+                continue;
+            };
+
+            let mut agreeing = 0;
+            for (source, price) in &prices {
+                let deviation_bps = ((*price - median) / median).abs() * Decimal::from(10_000);
+                if deviation_bps <= tolerance {
+                    agreeing += 1;
+                } else {
+                    state.divergences.push(Divergence {
+                        pool,
+                        pair_id: reference.pair_id(),
+                        source: source.clone(),
+                        price: *price,
+                        median,
+                        deviation_bps,
+                    });
+                }
+            }
+
+            let mut pair = reference;
+            pair.price = median;
+
+            // USD liquidity from the first source that has it, or none
+            let usd = entries.iter()
+                .filter(|(_, p)| p.pair_key() == key)
+                .find(|(name, _)| !self.quote_liquidity_sources.contains(name));
+            let Some((_, usd)) = usd else {
+                state.untrusted.push(pair);
+                continue;
+            };
+            pair.liquidity = usd.liquidity;
+            if pair.price_usd.is_none() && usd.base_token.address == pair.base_token.address {
+                pair.price_usd = usd.price_usd;
+            }
+
+            if agreeing >= self.quorum {
+                state.trusted.push(pair);
+            } else {
+                state.untrusted.push(pair);
+            }
+        }
+
+        state
+    }
+}
+
+/// Median of a list of prices (mean of the middle two for even lengths)
+fn median(mut prices: Vec<Decimal>) -> Option<Decimal> {
+    if prices.is_empty() {
+        return None;
+    }
+    prices.sort();
+    let mid = prices.len() / 2;
+    if prices.len().is_multiple_of(2) {
+        Some((prices[mid - 1] + prices[mid]) / Decimal::from(2))
+    } else {
+        Some(prices[mid])
+    }
+}
+
+#[async_trait]
+impl PriceFeed for CompositePriceFeed {
+    async fn get_trading_pairs(&self) -> Vec<TradingPair> {
+        self.state.read().await.trusted.clone()
+    }
+
+    async fn get_price(&self, base: &str, quote: &str) -> Option<Decimal> {
+        self.state.read().await.trusted.iter()
+            .find(|p| p.base_token.symbol == base && p.quote_token.symbol == quote)
+            .map(|p| p.price)
+    }
+
+    async fn get_liquidity(&self, base: &str, quote: &str) -> Option<Decimal> {
+        self.state.read().await.trusted.iter()
+            .find(|p| p.base_token.symbol == base && p.quote_token.symbol == quote)
+            .map(|p| p.liquidity)
+    }
+
+    async fn refresh(&self) -> anyhow::Result<()> {
+        let mut refreshed = 0;
+        for (name, source) in &self.sources {
+            match source.refresh().await {
+                Ok(()) => refreshed += 1,
+                Err(e) => warn!("Composite source '{}' failed to refresh: {}", name, e),
+            }
+        }
+        if refreshed == 0 && !self.sources.is_empty() {
+            return Err(anyhow::anyhow!("All {} composite sources failed", self.sources.len()));
+        }
+
+        let mut state = self.merge().await;
+        let outcome = self.filter.apply(std::mem::take(&mut state.trusted));
+        outcome.log("Composite");
+        state.trusted = outcome.kept;
+
+        for d in &state.divergences {
+            warn!(
+                "Source '{}' diverged on {} ({}): {} vs median {} ({:.0} bps)",
+                d.source, d.pair_id, d.pool, d.price, d.median, d.deviation_bps
+            );
+        }
+        info!(
+            "Composite feed refreshed: {} trusted, {} untrusted pools",
+            state.trusted.len(),
+            state.untrusted.len()
+        );
+
+        *self.state.write().await = state;
         Ok(())
     }
 }
@@ -351,6 +1383,15 @@ impl PriceFeed for MetisPriceFeed {
 // MockPriceFeed - For testing purposes
 // ============================================================================
 
+/// Pool addresses of the default mock pairs: WETH/USDC on Netswap and
+/// Tethys, then METIS/USDC on Netswap and Tethys
+pub const MOCK_POOLS: [&str; 4] = [
+    "0x00000000000000000000000000000000000000a1",
+    "0x00000000000000000000000000000000000000a2",
+    "0x00000000000000000000000000000000000000a3",
+    "0x00000000000000000000000000000000000000a4",
+];
+
 /// Mock price feed for testing and development
 #[derive(Debug, Clone)]
 pub struct MockPriceFeed {
@@ -360,37 +1401,40 @@ pub struct MockPriceFeed {
 impl MockPriceFeed {
     pub fn new() -> Self {
         // Create some mock trading pairs for testing
-        let weth = Token::new("WETH", "Wrapped Ether", 18, "0x420000000000000000000000000000000000000a");
-        let usdc = Token::new("USDC", "USD Coin", 6, "0xEA32A96608495e54156Ae48931A7c20f0dcc1a21");
-        let metis = Token::new("METIS", "Metis Token", 18, "0xDeadDeAddeAddEAddeadDEaDDEAdDeaDDeAD0000");
+        let address = |s: &str| s.parse::<Address>().expect("mock address is valid");
+        let weth = Token::new("WETH", "Wrapped Ether", 18, address("0x420000000000000000000000000000000000000a"));
+        let usdc = Token::new("USDC", "USD Coin", 6, address("0xEA32A96608495e54156Ae48931A7c20f0dcc1a21"));
+        let metis = Token::new("METIS", "Metis Token", 18, address("0xDeadDeAddeAddEAddeadDEaDDEAdDeaDDeAD0000"));
 
-        let netswap = Exchange::new("netswap", "Metis", "0x1E876cCe41B7b844FDe09E38Fa1cf00f213bFf56");
-        let tethys = Exchange::new("tethys", "Metis", "0x81b9FA50D5f5155Ee17817C21702C3AE4780AD09");
+        let [netswap, tethys]: [Exchange; 2] = known_metis_exchanges().try_into().unwrap();
+        let pool = |pool: &str, exchange: &Exchange, base: &Token| {
+            Pool::new(address(pool), exchange.clone(), base.clone(), usdc.clone())
+        };
 
         let pairs = vec![
             TradingPair::new(
-                weth.clone(), usdc.clone(), netswap.clone(),
+                weth.clone(), usdc.clone(), pool(MOCK_POOLS[0], &netswap, &weth),
                 Decimal::from(1850),
                 Decimal::from(500000),
                 Decimal::from(270),
                 Decimal::from(500000),
             ),
             TradingPair::new(
-                weth.clone(), usdc.clone(), tethys.clone(),
+                weth.clone(), usdc.clone(), pool(MOCK_POOLS[1], &tethys, &weth),
                 Decimal::from(1852),
                 Decimal::from(350000),
                 Decimal::from(189),
                 Decimal::from(350000),
             ),
             TradingPair::new(
-                metis.clone(), usdc.clone(), netswap.clone(),
+                metis.clone(), usdc.clone(), pool(MOCK_POOLS[2], &netswap, &metis),
                 Decimal::from(85),
                 Decimal::from(200000),
                 Decimal::from(2353),
                 Decimal::from(200000),
             ),
             TradingPair::new(
-                metis.clone(), usdc.clone(), tethys.clone(),
+                metis.clone(), usdc.clone(), pool(MOCK_POOLS[3], &tethys, &metis),
                 Decimal::from(84),
                 Decimal::from(150000),
                 Decimal::from(1786),
@@ -400,6 +1444,11 @@ impl MockPriceFeed {
 
         MockPriceFeed { pairs }
     }
+
+    /// Create a mock feed serving the given pairs
+    pub fn with_pairs(pairs: Vec<TradingPair>) -> Self {
+        MockPriceFeed { pairs }
+    }
 }
 
 impl Default for MockPriceFeed {
@@ -435,6 +1484,10 @@ impl PriceFeed for MockPriceFeed {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::multicall::MULTICALL3_ADDRESS;
+    use crate::test_util::{abi_string, serve_aggregate3, MockHttpServer, MockResponse, MockRpcServer};
+    use rust_decimal_macros::dec;
+    use serde_json::{json, Value};
 
     #[tokio::test]
     async fn test_mock_price_feed() {
@@ -453,4 +1506,525 @@ mod tests {
         assert!(price.is_some());
         assert!(price.unwrap() > Decimal::ZERO);
     }
+
+    const POOL: &str = "0x5ae3ee7fbb3cb28c17e7adc3a6ae605ae2465091";
+    const WETH: &str = "0x420000000000000000000000000000000000000a";
+    const USDC: &str = "0xea32a96608495e54156ae48931a7c20f0dcc1a21";
+
+    fn address(s: &str) -> Address {
+        s.parse().unwrap()
+    }
+
+    /// A drained WETH/USDC pool: 1 wei WETH against 10^23 USDC
+    const DUST_POOL: &str = "0x00000000000000000000000000000000000000d5";
+
+    /// Contract state of a WETH/USDC pool holding 10 WETH and 18,500 USDC,
+    /// and of the dust pool
+    fn pool_call(to: &str, data: &[u8]) -> Option<Vec<u8>> {
+        let selector: [u8; 4] = data.try_into().ok()?;
+        match (to, selector) {
+            (DUST_POOL, abi::SELECTOR_TOKEN0) => Some(abi::encode_address(WETH).unwrap().to_vec()),
+            (DUST_POOL, abi::SELECTOR_TOKEN1) => Some(abi::encode_address(USDC).unwrap().to_vec()),
+            (DUST_POOL, abi::SELECTOR_GET_RESERVES) => {
+                let mut out = Vec::new();
+                out.extend_from_slice(&abi::encode_u128(1));
+                out.extend_from_slice(&abi::encode_u128(100_000_000_000_000_000_000_000_000_000));
+                out.extend_from_slice(&abi::encode_u128(1_700_000_000));
+                Some(out)
+            }
+            (POOL, abi::SELECTOR_TOKEN0) => Some(abi::encode_address(WETH).unwrap().to_vec()),
+            (POOL, abi::SELECTOR_TOKEN1) => Some(abi::encode_address(USDC).unwrap().to_vec()),
+            (POOL, abi::SELECTOR_GET_RESERVES) => {
+                let mut out = Vec::new();
+                out.extend_from_slice(&abi::encode_u128(10_000_000_000_000_000_000));
+                out.extend_from_slice(&abi::encode_u128(18_500_000_000));
+                out.extend_from_slice(&abi::encode_u128(1_700_000_000));
+                Some(out)
+            }
+            (WETH, abi::SELECTOR_DECIMALS) => Some(abi::encode_u128(18).to_vec()),
+            (USDC, abi::SELECTOR_DECIMALS) => Some(abi::encode_u128(6).to_vec()),
+            (WETH, abi::SELECTOR_SYMBOL) => Some(abi_string("WETH")),
+            (USDC, abi::SELECTOR_SYMBOL) => Some(abi_string("m.USDC")),
+            _ => None,
+        }
+    }
+
+    /// JSON-RPC stand-in serving `pool_call` directly and via Multicall3
+    async fn mock_pool_rpc() -> MockRpcServer {
+        MockRpcServer::start(|method, params| match method {
+            "eth_blockNumber" => Ok(json!("0x3e8")),
+            "eth_call" => {
+                let to = params[0]["to"].as_str().unwrap().to_lowercase();
+                let data = abi::decode_hex(params[0]["data"].as_str().unwrap()).unwrap();
+
+                let output = if to == MULTICALL3_ADDRESS.to_lowercase() {
+                    assert_eq!(params[1], json!("0x3e8"), "pool reads must be pinned to one block");
+                    Some(serve_aggregate3(&data, pool_call))
+                } else {
+                    if to == POOL {
+                        assert_eq!(params[1], json!("0x3e8"), "pool reads must be pinned to one block");
+                    }
+                    pool_call(&to, &data)
+                };
+                output
+                    .map(|out| json!(abi::encode_hex(&out)))
+                    .ok_or((-32000, "execution reverted".to_string()))
+            }
+            _ => Err((-32601, "method not found".to_string())),
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_onchain_feed_reads_exact_reserves() {
+        let server = mock_pool_rpc().await;
+        let feed = OnChainPriceFeed::new(&server.url, vec![PoolConfig::new(address(POOL), "netswap")]);
+
+        feed.refresh().await.unwrap();
+        let pairs = feed.get_trading_pairs().await;
+
+        assert_eq!(pairs.len(), 1);
+        let pair = &pairs[0];
+        assert_eq!(pair.base_token.symbol, "WETH");
+        assert_eq!(pair.quote_token.decimals, 6);
+        assert_eq!(pair.reserve_base, dec!(10));
+        assert_eq!(pair.reserve_quote, dec!(18500));
+        assert_eq!(pair.reserve_block, Some(1000));
+        let (raw_base, raw_quote) = pair.raw_reserves.unwrap();
+        assert_eq!(raw_base.raw(), U256::from(10_000_000_000_000_000_000u128));
+        assert_eq!(raw_quote, pair.quote_token.amount(U256::from(18_500_000_000u64)));
+        assert_eq!(pair.price, dec!(1850));
+        assert_eq!(feed.get_price("WETH", "m.USDC").await, Some(dec!(1850)));
+    }
+
+    #[tokio::test]
+    async fn test_onchain_feed_with_multicall() {
+        let server = mock_pool_rpc().await;
+        let pools = vec![
+            PoolConfig::new(address(POOL), "netswap"),
+            PoolConfig::new(address("0x0000000000000000000000000000000000000001"), "tethys"),
+        ];
+
+        // A chain without Multicall3 reads pool by pool
+        let bare = Chain { multicall3: None, ..Chain::metis() };
+        assert!(OnChainPriceFeed::new(&server.url, pools.clone()).with_chain(&bare).multicall.is_none());
+
+        let feed = OnChainPriceFeed::new(&server.url, pools).with_chain(&Chain::metis());
+
+        feed.refresh().await.unwrap();
+        let pairs = feed.get_trading_pairs().await;
+
+        // The unknown pool reverts without affecting the good one
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].reserve_quote, dec!(18500));
+        assert_eq!(pairs[0].reserve_block, Some(1000));
+    }
+
+    fn mock_source(prices: &[(&str, Decimal)]) -> Arc<dyn PriceFeed> {
+        let weth = Token::new("WETH", "Wrapped Ether", 18, address(WETH));
+        let usdc = Token::new("m.USDC", "USD Coin", 6, address(USDC));
+        let pairs = prices.iter()
+            .map(|(pool, price)| TradingPair::new(
+                weth.clone(), usdc.clone(),
+                Pool::new(address(pool), Exchange::new("netswap", METIS_CHAIN_ID), weth.clone(), usdc.clone()),
+                *price, dec!(100000), dec!(10), dec!(18500),
+            ))
+            .collect();
+        Arc::new(MockPriceFeed::with_pairs(pairs))
+    }
+
+    #[tokio::test]
+    async fn test_composite_uses_median_and_reports_divergence() {
+        let feed = CompositePriceFeed::new(vec![
+            ("dexscreener".to_string(), mock_source(&[(POOL, dec!(1850))])),
+            ("onchain".to_string(), mock_source(&[(POOL, dec!(1851))])),
+            ("bad-api".to_string(), mock_source(&[(POOL, dec!(2500))])),
+        ])
+        .with_quorum(2);
+
+        feed.refresh().await.unwrap();
+
+        let pairs = feed.get_trading_pairs().await;
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].price, dec!(1851));
+
+        let divergences = feed.divergences().await;
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].source, "bad-api");
+    }
+
+    #[tokio::test]
+    async fn test_composite_withholds_untrusted_pools() {
+        let feed = CompositePriceFeed::new(vec![
+            ("dexscreener".to_string(), mock_source(&[(POOL, dec!(1850))])),
+            ("onchain".to_string(), mock_source(&[(&POOL.to_uppercase().replace("0X", "0x"), dec!(2000))])),
+        ]);
+
+        feed.refresh().await.unwrap();
+
+        // Two sources, 8% apart: neither agrees with the median
+        assert!(feed.get_trading_pairs().await.is_empty());
+        assert_eq!(feed.untrusted_pairs().await.len(), 1);
+        assert_eq!(feed.divergences().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_composite_takes_liquidity_from_usd_sources() {
+        const ONCHAIN_ONLY: &str = "0x00000000000000000000000000000000000000c2";
+
+        // Reserves-based liquidity in quote units, listed first
+        let onchain: Vec<TradingPair> = mock_source(&[(POOL, dec!(1850)), (ONCHAIN_ONLY, dec!(1850))])
+            .get_trading_pairs().await
+            .into_iter()
+            .map(|pair| TradingPair { liquidity: dec!(37), ..pair })
+            .collect();
+        let feed = CompositePriceFeed::new(vec![
+            ("onchain".to_string(), Arc::new(MockPriceFeed::with_pairs(onchain)) as Arc<dyn PriceFeed>),
+            ("dexscreener".to_string(), mock_source(&[(POOL, dec!(1851))])),
+        ])
+        .with_quote_liquidity_source("onchain")
+        .with_filter(PairFilter::permissive().with_min_liquidity(dec!(50000)));
+        feed.refresh().await.unwrap();
+
+        // The USD figure is checked; the pool only the chain reports is withheld
+        let pairs = feed.get_trading_pairs().await;
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].liquidity, dec!(100000));
+        let untrusted = feed.untrusted_pairs().await;
+        assert_eq!(untrusted.len(), 1);
+        assert_eq!(untrusted[0].pool.address, address(ONCHAIN_ONLY));
+    }
+
+    #[tokio::test]
+    async fn test_composite_merges_screener_and_onchain_units() {
+        const METIS: &str = "0xdeaddeaddeaddeaddeaddeaddeaddeaddead0000";
+        const PAIR: &str = "0x00000000000000000000000000000000000000c1";
+
+        // DEX Screener lists the pool METIS/WETH: 0.02 WETH, or $40, per METIS
+        let server = MockHttpServer::start(|_| {
+            let mut p = screener_pair("metis", "netswap", PAIR);
+            p["baseToken"] = json!({ "address": METIS, "symbol": "METIS" });
+            p["quoteToken"] = json!({ "address": WETH, "symbol": "WETH" });
+            p["priceUsd"] = json!("40");
+            p["priceNative"] = json!("0.02");
+            MockResponse::json(json!({ "pairs": [p] }))
+        })
+        .await;
+        let config = DexScreenerConfig { base_url: server.url.clone(), ..DexScreenerConfig::metis() };
+        let screener = DexScreenerFeed::with_config(config).with_token_registry(TokenRegistry::metis());
+
+        // On-chain reserves are token0 (WETH) / token1 (METIS): 50.05 METIS per WETH
+        let weth = Token::new("WETH", "Wrapped Ether", 18, address(WETH));
+        let metis = Token::new("METIS", "Metis Token", 18, address(METIS));
+        let pool = Pool::new(address(PAIR), Exchange::new("netswap", METIS_CHAIN_ID), weth.clone(), metis.clone());
+        let onchain = TradingPair::new(weth.clone(), metis.clone(), pool, dec!(50.05), dec!(0), dec!(100), dec!(5005))
+            .with_raw_reserves(weth.parse_amount(dec!(100)).unwrap(), metis.parse_amount(dec!(5005)).unwrap());
+
+        let feed = CompositePriceFeed::new(vec![
+            ("dexscreener".to_string(), Arc::new(screener) as Arc<dyn PriceFeed>),
+            ("onchain".to_string(), Arc::new(MockPriceFeed::with_pairs(vec![onchain]))),
+        ])
+        .with_quorum(2);
+        feed.refresh().await.unwrap();
+
+        // 0.02 vs 1/50.05 is ~10 bps apart: both agree, priced as DEX Screener lists it
+        assert!(feed.divergences().await.is_empty());
+        let pairs = feed.get_trading_pairs().await;
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].base_token.address, address(METIS));
+        assert_eq!(pairs[0].price.round_dp(4), dec!(0.0200));
+        assert_eq!(pairs[0].price_usd, Some(dec!(40)));
+    }
+
+    #[tokio::test]
+    async fn test_metis_cache_keeps_each_pool() {
+        let feed = MetisPriceFeed::new();
+        feed.store_pairs(MockPriceFeed::new().get_trading_pairs().await).await;
+
+        // Both WETH/USDC pools survive; symbol lookups pick the most liquid
+        assert_eq!(feed.cache.read().await.len(), 4);
+        assert_eq!(feed.get_price("WETH", "USDC").await, Some(Decimal::from(1850)));
+        assert_eq!(feed.get_liquidity("WETH", "USDC").await, Some(Decimal::from(500000)));
+        assert_eq!(
+            feed.get_pool_price(address(MOCK_POOLS[1])).await,
+            Some(Decimal::from(1852))
+        );
+
+        // Either orientation hits the same entries, inverted as asked
+        let (weth, usdc) = (address(WETH), address(USDC));
+        assert_eq!(feed.get_price("USDC", "WETH").await, Some(Decimal::ONE / Decimal::from(1850)));
+        assert_eq!(feed.get_liquidity("USDC", "WETH").await, Some(Decimal::from(500000)));
+        assert_eq!(feed.get_exchange_price("tethys", weth, usdc).await, Some(Decimal::from(1852)));
+        assert_eq!(feed.get_exchange_price("tethys", usdc, weth).await, Some(Decimal::ONE / Decimal::from(1852)));
+    }
+
+    fn screener_pair(chain: &str, dex: &str, pool: &str) -> Value {
+        json!({
+            "chainId": chain,
+            "dexId": dex,
+            "pairAddress": pool,
+            "baseToken": { "address": WETH, "symbol": "WETH" },
+            "quoteToken": { "address": USDC, "symbol": "USDC" },
+            "priceUsd": "1850.5",
+            "priceNative": "1850.5",
+            "liquidity": { "usd": 250000.0, "base": 60.0, "quote": 125000.0 },
+            "volume": { "h24": 98765.4, "h6": 1200.5, "h1": 300.0, "m5": 0 },
+            "txns": { "h24": { "buys": 120, "sells": 80 }, "h1": { "buys": 3, "sells": 1 } },
+            "priceChange": { "h24": -2.35, "h1": 0.1 },
+            "fdv": 1234567.89,
+            "pairCreatedAt": 1700000000000i64,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_dex_screener_feed_applies_chain_and_allowlist() {
+        let server = MockHttpServer::start(|_| {
+            MockResponse::json(json!({ "pairs": [
+                screener_pair("arbitrum", "camelot", "0x00000000000000000000000000000000000000a1"),
+                screener_pair("arbitrum", "sushiswap", "0x00000000000000000000000000000000000000a2"),
+                screener_pair("metis", "camelot", "0x00000000000000000000000000000000000000a3"),
+            ]}))
+        })
+        .await;
+
+        let config = DexScreenerConfig {
+            chain_id: "arbitrum".to_string(),
+            evm_chain_id: 42161,
+            dex_allowlist: vec!["camelot".to_string()],
+            discovery: vec![DiscoveryStrategy::Search { terms: vec!["weth".to_string()] }],
+            rpc_url: "http://127.0.0.1:1".to_string(),
+            exchanges: Vec::new(),
+            pool_fees: BTreeMap::new(),
+            base_url: server.url.clone(),
+        }
+        .with_pool_fee(address("0x00000000000000000000000000000000000000A1"), 5);
+        // The shared cache is keyed by chain: only Arbitrum entries resolve
+        let tokens = TokenRegistry::new(&config.rpc_url);
+        tokens.seed(known_metis_tokens().into_iter().map(|t| t.with_chain_id(42161))).await;
+        let feed = DexScreenerFeed::with_config(config).with_token_registry(tokens);
+
+        feed.refresh().await.unwrap();
+        let pairs = feed.get_trading_pairs().await;
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].exchange().name, "camelot");
+        assert_eq!(pairs[0].exchange().chain_id, 42161);
+        assert_eq!(pairs[0].pool.address, address("0x00000000000000000000000000000000000000a1"));
+        assert_eq!(pairs[0].exchange().router_address, None);
+        assert_eq!(pairs[0].pool.fee_bps, 5);
+        assert_eq!(pairs[0].base_token.decimals, 18);
+        assert_eq!((pairs[0].base_token.chain_id, pairs[0].quote_token.chain_id), (42161, 42161));
+        assert_eq!((pairs[0].price, pairs[0].price_usd), (dec!(1850.5), Some(dec!(1850.5))));
+
+        let market = pairs[0].market.as_ref().unwrap();
+        assert_eq!(market.volume_usd.h24, dec!(98765.4));
+        assert_eq!(market.txns.h24, TxnCounts { buys: 120, sells: 80 });
+        assert_eq!(market.txns.m5, TxnCounts::default());
+        assert_eq!(market.price_change_percent.h24, dec!(-2.35));
+        assert_eq!(market.fdv_usd, Some(dec!(1234567.89)));
+        assert_eq!(market.pair_created_at.unwrap().timestamp(), 1_700_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_watchlist_batches_and_merges_with_search() {
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        let server = MockHttpServer::start(move |req| {
+            seen.lock().unwrap().push(req.path.clone());
+            let pairs: Vec<Value> = match req.path.strip_prefix("/pairs/metis/") {
+                // Watched pools live on a DEX outside the allowlist
+                Some(addrs) => addrs.split(',').map(|a| screener_pair("metis", "hercules", a)).collect(),
+                None => vec![screener_pair("metis", "netswap", POOL)],
+            };
+            MockResponse::json(json!({ "pairs": pairs }))
+        })
+        .await;
+
+        let watched: Vec<Address> = (1..=31).map(|i| address(&format!("0x{:040x}", i))).collect();
+        let config = DexScreenerConfig { base_url: server.url.clone(), ..DexScreenerConfig::metis() }
+            .with_watchlist(watched);
+        let feed = DexScreenerFeed::with_config(config).with_token_registry(TokenRegistry::metis());
+
+        feed.refresh().await.unwrap();
+
+        let paths = requests.lock().unwrap().clone();
+        let batches: Vec<&String> = paths.iter().filter(|p| p.starts_with("/pairs/")).collect();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].matches(',').count(), DEX_SCREENER_PAIRS_BATCH_SIZE - 1);
+
+        let pairs = feed.get_trading_pairs().await;
+        assert_eq!(pairs.len(), 32);
+        assert_eq!(pairs.iter().filter(|p| p.exchange().name == "hercules").count(), 31);
+        assert_eq!(feed.endpoint_stats().await["pairs"].requests, 2);
+    }
+
+    #[tokio::test]
+    async fn test_token_crawl_expands_breadth_first() {
+        const METIS: &str = "0xdeaddeaddeaddeaddeaddeaddeaddeaddead0000";
+        const USDT: &str = "0xbb06dca3ae6887fabf931640f67cab3e3a16f4dc";
+
+        let server = MockHttpServer::start(|req| {
+            let pair = |pool: &str, base: &str, quote: &str| {
+                let mut p = screener_pair("metis", "netswap", pool);
+                p["baseToken"]["address"] = json!(base);
+                p["quoteToken"]["address"] = json!(quote);
+                p
+            };
+            let pairs = if req.path.starts_with("/pairs/") {
+                req.path.rsplit('/').next().unwrap().split(',')
+                    .map(|pool| pair(pool, WETH, USDC))
+                    .collect()
+            } else if req.path.contains(METIS) {
+                // Seed level: METIS/WETH, plus a pool on another chain
+                vec![pair("0x00000000000000000000000000000000000000b1", METIS, WETH), {
+                    let mut other = pair("0x00000000000000000000000000000000000000b9", METIS, USDT);
+                    other["chainId"] = json!("ethereum");
+                    other
+                }]
+            } else if req.path.contains(WETH) {
+                vec![pair("0x00000000000000000000000000000000000000b2", WETH, USDC)]
+            } else if req.path.contains(USDC) {
+                vec![pair("0x00000000000000000000000000000000000000b3", USDC, USDT)]
+            } else {
+                vec![]
+            };
+            MockResponse::json(json!({ "pairs": pairs }))
+        })
+        .await;
+
+        let crawl = |max_depth| {
+            let config = DexScreenerConfig {
+                base_url: server.url.clone(),
+                discovery: Vec::new(),
+                ..DexScreenerConfig::metis()
+            }
+            .with_token_crawl(vec![address(METIS)], max_depth, 10);
+            DexScreenerFeed::with_config(config).with_token_registry(TokenRegistry::metis())
+        };
+
+        // Depth 1 reaches WETH's pools but not USDC's
+        let feed = crawl(1);
+        feed.refresh().await.unwrap();
+        assert_eq!(feed.crawled_pools().await.len(), 2);
+
+        let feed = crawl(2);
+        feed.refresh().await.unwrap();
+        assert_eq!(feed.get_trading_pairs().await.len(), 3);
+
+        // Later refreshes go through the pairs endpoint
+        feed.refresh().await.unwrap();
+        let stats = feed.endpoint_stats().await;
+        assert_eq!(stats["tokens"].requests, 3);
+        assert_eq!(stats["pairs"].requests, 1);
+        assert_eq!(feed.get_trading_pairs().await.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_token_crawl_requests_each_seed_once() {
+        let paths = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = paths.clone();
+        let server = MockHttpServer::start(move |req| {
+            seen.lock().unwrap().push(req.path.clone());
+            MockResponse::json(json!({ "pairs": [] }))
+        })
+        .await;
+
+        // Duplicates that are not next to each other
+        let seeds = vec![address(WETH), address(USDC), address(WETH), address(USDC)];
+        let config = DexScreenerConfig { base_url: server.url.clone(), discovery: Vec::new(), ..DexScreenerConfig::metis() }
+            .with_token_crawl(seeds, 1, 10);
+        DexScreenerFeed::with_config(config).refresh().await.unwrap();
+
+        let paths = paths.lock().unwrap();
+        assert_eq!(paths.len(), 1);
+        let requested: Vec<&str> = paths[0].rsplit('/').next().unwrap().split(',').collect();
+        assert_eq!(requested.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_screener_pairs_listed_both_ways_compare_in_native_units() {
+        const METIS: &str = "0xdeaddeaddeaddeaddeaddeaddeaddeaddead0000";
+
+        // 1 METIS = 0.02 WETH on both DEXes, listed once each way round;
+        // the USD prices ($40 METIS, $2000 WETH) are not comparable
+        let server = MockHttpServer::start(|_| {
+            let pair = |dex: &str, pool: &str, base: &str, quote: &str, usd: &str, native: &str| {
+                let mut p = screener_pair("metis", dex, pool);
+                p["baseToken"]["address"] = json!(base);
+                p["quoteToken"]["address"] = json!(quote);
+                p["priceUsd"] = json!(usd);
+                p["priceNative"] = json!(native);
+                p
+            };
+            MockResponse::json(json!({ "pairs": [
+                pair("netswap", "0x00000000000000000000000000000000000000c1", METIS, WETH, "40", "0.02"),
+                pair("tethys", "0x00000000000000000000000000000000000000c2", WETH, METIS, "2000", "50"),
+            ]}))
+        })
+        .await;
+
+        let config = DexScreenerConfig { base_url: server.url.clone(), ..DexScreenerConfig::metis() };
+        let feed = DexScreenerFeed::with_config(config).with_token_registry(TokenRegistry::metis());
+        feed.refresh().await.unwrap();
+
+        let pairs = feed.get_trading_pairs().await;
+        assert_eq!(pairs.len(), 2);
+        let filter = PairFilter::permissive();
+        let found = crate::detection::find_price_differences(&pairs, &filter, chrono::Utc::now(), dec!(0.1));
+        assert!(found.is_empty(), "{:?}", found);
+
+        // The canonical (WETH/METIS) quote carries WETH's USD price
+        let canonical = pairs.iter().find(|p| p.exchange().name == "netswap").unwrap().canonical();
+        assert_eq!((canonical.price, canonical.price_usd), (dec!(50), Some(dec!(2000))));
+
+        let (metis, weth) = (address(METIS), address(WETH));
+        for exchange in ["netswap", "tethys"] {
+            assert_eq!(feed.get_exchange_price(exchange, metis, weth).await, Some(dec!(0.02)));
+            assert_eq!(feed.get_exchange_price(exchange, weth, metis).await, Some(dec!(50)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pool_and_exchange_lookups() {
+        let feed = MockPriceFeed::new();
+        let netswap_pool = address(MOCK_POOLS[0]);
+
+        assert_eq!(feed.get_pool_price(netswap_pool).await, Some(Decimal::from(1850)));
+        assert_eq!(feed.get_pool_liquidity(netswap_pool).await, Some(Decimal::from(500000)));
+        assert_eq!(
+            feed.get_exchange_price(
+                "tethys",
+                address("0x420000000000000000000000000000000000000A"),
+                address("0xEA32A96608495e54156Ae48931A7c20f0dcc1a21"),
+            ).await,
+            Some(Decimal::from(1852))
+        );
+        assert_eq!(feed.get_exchange_price("hercules", address(WETH), address(USDC)).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_onchain_feed_skips_dust_pools() {
+        let server = mock_pool_rpc().await;
+        let feed = OnChainPriceFeed::new(
+            &server.url,
+            vec![PoolConfig::new(address(DUST_POOL), "netswap"), PoolConfig::new(address(POOL), "netswap")],
+        );
+
+        // 10^23 USDC per 10^-18 WETH does not fit a Decimal; the pool is
+        // skipped instead of taking the refresh down
+        feed.refresh().await.unwrap();
+        let pairs = feed.get_trading_pairs().await;
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].pool.address, address(POOL));
+    }
+
+    #[tokio::test]
+    async fn test_onchain_feed_fails_when_no_pool_readable() {
+        let server = mock_pool_rpc().await;
+        let feed = OnChainPriceFeed::new(
+            &server.url,
+            vec![PoolConfig::new(address("0x0000000000000000000000000000000000000001"), "netswap")],
+        );
+
+        assert!(feed.refresh().await.is_err());
+    }
 }
@@ -0,0 +1,248 @@
+//! replay.rs - Recording and replay of price feed snapshots
+//!
+//! RecordingPriceFeed wraps any feed and appends each refreshed pair set to a
+//! JSONL file; ReplayPriceFeed steps through such a file so a run can be
+//! reproduced offline without network access.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::{debug, info};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, RwLock};
+
+use crate::address::Address;
+use crate::models::TradingPair;
+use crate::price_feed::PriceFeed;
+
+/// One refreshed pair set, as written to a recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub timestamp: DateTime<Utc>,
+    pub pairs: Vec<TradingPair>,
+}
+
+// ============================================================================
+// RecordingPriceFeed - Persists every refresh
+// ============================================================================
+
+/// Wraps a feed and appends a Snapshot line after every successful refresh
+pub struct RecordingPriceFeed {
+    inner: Arc<dyn PriceFeed>,
+    path: PathBuf,
+    // Serializes appends so concurrent refreshes never interleave lines
+    write_lock: Mutex<()>,
+}
+
+impl RecordingPriceFeed {
+    pub fn new(inner: Arc<dyn PriceFeed>, path: impl AsRef<Path>) -> Self {
+        RecordingPriceFeed {
+            inner,
+            path: path.as_ref().to_path_buf(),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    async fn append(&self, snapshot: &Snapshot) -> anyhow::Result<()> {
+        let mut line = serde_json::to_string(snapshot)?;
+        line.push('\n');
+
+        let _guard = self.write_lock.lock().await;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PriceFeed for RecordingPriceFeed {
+    async fn get_trading_pairs(&self) -> Vec<TradingPair> {
+        self.inner.get_trading_pairs().await
+    }
+
+    async fn get_price(&self, base: &str, quote: &str) -> Option<Decimal> {
+        self.inner.get_price(base, quote).await
+    }
+
+    async fn get_liquidity(&self, base: &str, quote: &str) -> Option<Decimal> {
+        self.inner.get_liquidity(base, quote).await
+    }
+
+    async fn get_pool_price(&self, pool_address: Address) -> Option<Decimal> {
+        self.inner.get_pool_price(pool_address).await
+    }
+
+    async fn get_pool_liquidity(&self, pool_address: Address) -> Option<Decimal> {
+        self.inner.get_pool_liquidity(pool_address).await
+    }
+
+    async fn get_exchange_price(
+        &self,
+        exchange: &str,
+        base_address: Address,
+        quote_address: Address,
+    ) -> Option<Decimal> {
+        self.inner.get_exchange_price(exchange, base_address, quote_address).await
+    }
+
+    async fn refresh(&self) -> anyhow::Result<()> {
+        self.inner.refresh().await?;
+
+        let snapshot = Snapshot {
+            timestamp: Utc::now(),
+            pairs: self.inner.get_trading_pairs().await,
+        };
+        self.append(&snapshot).await?;
+        debug!("Recorded {} pairs to {}", snapshot.pairs.len(), self.path.display());
+        Ok(())
+    }
+}
+
+// ============================================================================
+// ReplayPriceFeed - Steps through a recording
+// ============================================================================
+
+/// Error returned by `ReplayPriceFeed::refresh` once every snapshot was served
+#[derive(Debug, thiserror::Error)]
+#[error("replay exhausted after {0} snapshots")]
+pub struct ReplayExhausted(pub usize);
+
+/// Serves recorded snapshots in order, advancing one per refresh
+pub struct ReplayPriceFeed {
+    snapshots: Vec<Snapshot>,
+    // Index of the next snapshot to serve
+    cursor: RwLock<usize>,
+    current: RwLock<Option<Snapshot>>,
+}
+
+impl ReplayPriceFeed {
+    pub fn new(snapshots: Vec<Snapshot>) -> Self {
+        ReplayPriceFeed {
+            snapshots,
+            cursor: RwLock::new(0),
+            current: RwLock::new(None),
+        }
+    }
+
+    /// Load a JSONL recording written by RecordingPriceFeed
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let raw = std::fs::read_to_string(path)?;
+
+        let snapshots = raw
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .map_err(|e| anyhow::anyhow!("{}:{}: {}", path.display(), i + 1, e))
+            })
+            .collect::<anyhow::Result<Vec<Snapshot>>>()?;
+
+        info!("Loaded {} snapshots from {}", snapshots.len(), path.display());
+        Ok(Self::new(snapshots))
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Snapshots not yet served
+    pub async fn remaining(&self) -> usize {
+        self.snapshots.len() - *self.cursor.read().await
+    }
+
+    /// Recording time of the snapshot currently served
+    pub async fn current_timestamp(&self) -> Option<DateTime<Utc>> {
+        self.current.read().await.as_ref().map(|s| s.timestamp)
+    }
+}
+
+#[async_trait]
+impl PriceFeed for ReplayPriceFeed {
+    async fn get_trading_pairs(&self) -> Vec<TradingPair> {
+        self.current.read().await.as_ref().map(|s| s.pairs.clone()).unwrap_or_default()
+    }
+
+    async fn get_price(&self, base: &str, quote: &str) -> Option<Decimal> {
+        self.get_trading_pairs().await.into_iter()
+            .filter(|p| p.base_token.symbol == base && p.quote_token.symbol == quote)
+            .max_by_key(|p| p.liquidity)
+            .map(|p| p.price)
+    }
+
+    async fn get_liquidity(&self, base: &str, quote: &str) -> Option<Decimal> {
+        self.get_trading_pairs().await.into_iter()
+            .filter(|p| p.base_token.symbol == base && p.quote_token.symbol == quote)
+            .map(|p| p.liquidity)
+            .max()
+    }
+
+    async fn refresh(&self) -> anyhow::Result<()> {
+        let mut cursor = self.cursor.write().await;
+        let snapshot = self.snapshots.get(*cursor).cloned().ok_or(ReplayExhausted(*cursor))?;
+
+        debug!("Replaying snapshot {}/{} ({})", *cursor + 1, self.snapshots.len(), snapshot.timestamp);
+        *self.current.write().await = Some(snapshot);
+        *cursor += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::{find_price_differences, DEFAULT_MIN_SPREAD_PERCENT};
+    use crate::filter::PairFilter;
+    use crate::price_feed::MockPriceFeed;
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.jsonl");
+
+        let recorder = RecordingPriceFeed::new(Arc::new(MockPriceFeed::new()), &path);
+        recorder.refresh().await.unwrap();
+        recorder.refresh().await.unwrap();
+
+        let replay = ReplayPriceFeed::open(&path).unwrap();
+        assert_eq!(replay.len(), 2);
+        assert!(replay.get_trading_pairs().await.is_empty());
+
+        replay.refresh().await.unwrap();
+        let pairs = replay.get_trading_pairs().await;
+        assert_eq!(pairs.len(), 4);
+        assert_eq!(replay.get_price("WETH", "USDC").await, Some(Decimal::from(1850)));
+        assert!(replay.current_timestamp().await.is_some());
+
+        // Detection sees exactly what the recorded run saw, judged at recording time
+        let live = MockPriceFeed::new().get_trading_pairs().await;
+        let filter = PairFilter::default().with_max_staleness_seconds(60);
+        let recorded_at = replay.current_timestamp().await.unwrap();
+        assert_eq!(
+            find_price_differences(&pairs, &filter, recorded_at, DEFAULT_MIN_SPREAD_PERCENT),
+            find_price_differences(&live, &filter, Utc::now(), DEFAULT_MIN_SPREAD_PERCENT)
+        );
+
+        replay.refresh().await.unwrap();
+        assert_eq!(replay.remaining().await, 0);
+        let err = replay.refresh().await.unwrap_err();
+        assert!(err.downcast_ref::<ReplayExhausted>().is_some());
+    }
+}
@@ -0,0 +1,191 @@
+//! rpc.rs - Minimal JSON-RPC client for EVM chains
+//!
+//! Direct on-chain reads (eth_blockNumber, eth_call) over HTTP
+
+use log::debug;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::abi::{self, AbiError};
+
+/// Public Metis Andromeda JSON-RPC endpoint
+pub const METIS_RPC_URL: &str = "https://andromeda.metis.io/?owner=1088";
+
+/// Errors returned by the JSON-RPC client
+#[derive(Debug, Error)]
+pub enum RpcError {
+    #[error("transport error: {0}")]
+    Transport(#[from] reqwest::Error),
+
+    #[error("rpc error {code}: {message}")]
+    Rpc { code: i64, message: String },
+
+    #[error("invalid response: {0}")]
+    InvalidResponse(String),
+
+    #[error(transparent)]
+    Abi(#[from] AbiError),
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse {
+    result: Option<Value>,
+    error: Option<RpcErrorObject>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcErrorObject {
+    code: i64,
+    message: String,
+}
+
+/// Block at which a call is executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockTag {
+    Latest,
+    Number(u64),
+}
+
+impl BlockTag {
+    fn to_param(self) -> String {
+        match self {
+            BlockTag::Latest => "latest".to_string(),
+            BlockTag::Number(n) => abi::format_quantity(n),
+        }
+    }
+}
+
+/// JSON-RPC client over HTTP
+#[derive(Debug, Clone)]
+pub struct RpcClient {
+    client: Client,
+    url: String,
+    next_id: Arc<AtomicU64>,
+}
+
+impl RpcClient {
+    /// Create a new client for the given endpoint
+    pub fn new(url: &str) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        RpcClient {
+            client,
+            url: url.to_string(),
+            next_id: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    /// Endpoint this client talks to
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Send a raw JSON-RPC request and deserialize its result
+    pub async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, RpcError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+
+        debug!("RPC {} -> {}", method, self.url);
+
+        let response: RpcResponse = self.client
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if let Some(err) = response.error {
+            return Err(RpcError::Rpc { code: err.code, message: err.message });
+        }
+
+        let result = response.result
+            .ok_or_else(|| RpcError::InvalidResponse(format!("missing result for {}", method)))?;
+
+        serde_json::from_value(result)
+            .map_err(|e| RpcError::InvalidResponse(format!("{}: {}", method, e)))
+    }
+
+    /// Get the latest block number
+    pub async fn block_number(&self) -> Result<u64, RpcError> {
+        let hex: String = self.request("eth_blockNumber", json!([])).await?;
+        Ok(abi::parse_quantity(&hex)?)
+    }
+
+    /// Execute a read-only call against a contract
+    pub async fn eth_call(
+        &self,
+        to: &str,
+        data: &[u8],
+        block: BlockTag,
+    ) -> Result<Vec<u8>, RpcError> {
+        let params = json!([
+            { "to": to, "data": abi::encode_hex(data) },
+            block.to_param(),
+        ]);
+        let hex: String = self.request("eth_call", params).await?;
+        Ok(abi::decode_hex(&hex)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockRpcServer;
+
+    #[tokio::test]
+    async fn test_block_number_and_call() {
+        let server = MockRpcServer::start(|method, params| match method {
+            "eth_blockNumber" => Ok(json!("0x10")),
+            "eth_call" => {
+                assert_eq!(params[1], json!("0x10"));
+                Ok(json!(abi::encode_hex(&abi::encode_u128(6))))
+            }
+            _ => Err((-32601, "method not found".to_string())),
+        })
+        .await;
+
+        let rpc = RpcClient::new(&server.url);
+        let block = rpc.block_number().await.unwrap();
+        assert_eq!(block, 16);
+
+        let data = rpc
+            .eth_call("0x0000000000000000000000000000000000000001", &[0x31, 0x3c, 0xe5, 0x67], BlockTag::Number(block))
+            .await
+            .unwrap();
+        assert_eq!(abi::decode_u8(&data).unwrap(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_rpc_error_is_surfaced() {
+        let server = MockRpcServer::start(|_, _| Err((-32000, "execution reverted".to_string()))).await;
+
+        let rpc = RpcClient::new(&server.url);
+        match rpc.block_number().await {
+            Err(RpcError::Rpc { code, message }) => {
+                assert_eq!(code, -32000);
+                assert_eq!(message, "execution reverted");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! simulation.rs - Constant-product execution of arbitrage routes
+//!
+//! Walks an ArbitrageRoute leg by leg through each pool's reserves
+//! with UniswapV2's `getAmountOut`, rounding exactly as the pair contract
+//! does, and reports what every swap returns and how far it moves the
+//! price. Opportunities built from a simulation carry executable amounts
+//! rather than mid-price spreads.
+
+use primitive_types::{U256, U512};
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+use crate::amount::{AmountError, TokenAmount};
+use crate::models::{ArbitrageLeg, ArbitrageOpportunity, ArbitrageRoute};
+
+/// Basis points in 100%
+const BPS_DENOMINATOR: u32 = 10_000;
+
+/// Why a single swap cannot be executed (UniswapV2 revert reasons)
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum SwapError {
+    #[error("insufficient input amount")]
+    InsufficientInputAmount,
+
+    #[error("insufficient liquidity")]
+    InsufficientLiquidity,
+
+    #[error("fee of {0} bps leaves nothing to swap")]
+    InvalidFee(u32),
+
+    #[error("swap amounts overflow")]
+    Overflow,
+}
+
+/// Why a route cannot be simulated
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum SimulationError {
+    #[error("leg {0} has no pool reserves")]
+    MissingReserves(usize),
+
+    #[error("leg {leg}: {source}")]
+    Swap { leg: usize, source: SwapError },
+
+    #[error(transparent)]
+    Amount(#[from] AmountError),
+}
+
+/// Output of a UniswapV2 swap of `amount_in`, rounded down like
+/// `UniswapV2Library.getAmountOut`, with the fee in basis points
+pub fn get_amount_out(amount_in: U256, reserve_in: U256, reserve_out: U256, fee_bps: u32) -> Result<U256, SwapError> {
+    if amount_in.is_zero() {
+        return Err(SwapError::InsufficientInputAmount);
+    }
+    if reserve_in.is_zero() || reserve_out.is_zero() {
+        return Err(SwapError::InsufficientLiquidity);
+    }
+    if fee_bps >= BPS_DENOMINATOR {
+        return Err(SwapError::InvalidFee(fee_bps));
+    }
+
+    let amount_in_with_fee = amount_in.full_mul(U256::from(BPS_DENOMINATOR - fee_bps));
+    let numerator = amount_in_with_fee
+        .checked_mul(U512::from(reserve_out))
+        .ok_or(SwapError::Overflow)?;
+    let denominator = reserve_in.full_mul(U256::from(BPS_DENOMINATOR)) + amount_in_with_fee;
+    U256::try_from(numerator / denominator).map_err(|_| SwapError::Overflow)
+}
+
+/// One executed swap
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegSimulation {
+    pub amount_in: TokenAmount,
+    pub amount_out: TokenAmount,
+    /// Pool price (to per from) before the swap
+    pub mid_price: Decimal,
+    /// Price actually received (to per from)
+    pub execution_price: Decimal,
+    /// Shortfall of the execution price against the fee-adjusted mid
+    /// price, in percent; the cost of moving along the curve
+    pub price_impact_percent: Decimal,
+}
+
+impl LegSimulation {
+    fn simulate(index: usize, leg: &ArbitrageLeg, amount_in: TokenAmount) -> Result<Self, SimulationError> {
+        let (reserve_in, reserve_out) = leg.reserves.ok_or(SimulationError::MissingReserves(index))?;
+        let raw_out = get_amount_out(amount_in.raw(), reserve_in.raw(), reserve_out.raw(), leg.pool.fee_bps)
+            .map_err(|source| SimulationError::Swap { leg: index, source })?;
+        let amount_out = TokenAmount::new(raw_out, leg.to_token.decimals);
+
+        // Reported prices only: computed from the raw amounts and rounded,
+        // so reserves too large for an exact Decimal cannot fail the swap
+        let ratio = |out: TokenAmount, into: TokenAmount| out.ratio_lossy(&into).unwrap_or(Decimal::ZERO);
+        let mid_price = ratio(reserve_out, reserve_in);
+        let execution_price = ratio(amount_out, amount_in);
+        let quoted = leg.pool.amount_after_fee(mid_price);
+        let price_impact_percent = execution_price.checked_div(quoted)
+            .map(|fraction| (Decimal::ONE - fraction) * Decimal::from(100))
+            .unwrap_or(Decimal::ZERO);
+
+        Ok(LegSimulation { amount_in, amount_out, mid_price, execution_price, price_impact_percent })
+    }
+}
+
+/// A route executed against current reserves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteSimulation {
+    pub legs: Vec<LegSimulation>,
+}
+
+impl RouteSimulation {
+    /// Amount of the start token put in
+    pub fn amount_in(&self) -> TokenAmount {
+        self.legs[0].amount_in
+    }
+
+    /// Amount of the start token received at the end of the route
+    pub fn amount_out(&self) -> TokenAmount {
+        self.legs[self.legs.len() - 1].amount_out
+    }
+
+    pub fn is_profitable(&self) -> bool {
+        self.amount_out().raw() > self.amount_in().raw()
+    }
+
+    /// Largest price impact of any leg, in percent
+    pub fn max_price_impact_percent(&self) -> Decimal {
+        self.legs.iter().map(|l| l.price_impact_percent).max().unwrap_or(Decimal::ZERO)
+    }
+
+    /// Amount entering each leg followed by the route's output
+    pub fn swap_amounts(&self) -> Vec<TokenAmount> {
+        let mut amounts: Vec<TokenAmount> = self.legs.iter().map(|l| l.amount_in).collect();
+        amounts.push(self.amount_out());
+        amounts
+    }
+
+    /// Opportunity with the simulated amounts, exact ones included;
+    /// `gas_cost` is in units of the start token
+    pub fn to_opportunity(&self, route: ArbitrageRoute, gas_cost: Decimal) -> Result<ArbitrageOpportunity, AmountError> {
+        let input = self.amount_in().to_decimal()?;
+        let output = self.amount_out().to_decimal()?;
+        let gross_profit = output - input;
+        Ok(ArbitrageOpportunity::new(route, input, output, gross_profit, gross_profit - gas_cost, gas_cost)
+            .with_swap_amounts(self.swap_amounts()))
+    }
+}
+
+/// Execute `route` with `amount_in` of its start token, leg by leg
+pub fn simulate_route(route: &ArbitrageRoute, amount_in: TokenAmount) -> Result<RouteSimulation, SimulationError> {
+    let start = route.start_token();
+    if amount_in.decimals() != start.decimals {
+        return Err(AmountError::DecimalsMismatch(amount_in.decimals(), start.decimals).into());
+    }
+
+    let mut legs: Vec<LegSimulation> = Vec::with_capacity(route.total_hops());
+    let mut amount = amount_in;
+    for (index, leg) in route.legs().iter().enumerate() {
+        let simulated = LegSimulation::simulate(index, leg, amount)?;
+        amount = simulated.amount_out;
+        legs.push(simulated);
+    }
+    Ok(RouteSimulation { legs })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::METIS_CHAIN_ID;
+    use crate::models::{Exchange, Pool, Token};
+    use crate::test_util::address;
+    use rust_decimal_macros::dec;
+
+    fn e18(n: u64) -> U256 {
+        U256::from(n) * U256::exp10(18)
+    }
+
+    #[test]
+    fn test_get_amount_out_matches_uniswap_v2() {
+        // 1 in against 100/200 at 0.3%: 997 * 200 / (100 * 1000 + 997), rounded down
+        let out = get_amount_out(e18(1), e18(100), e18(200), 30).unwrap();
+        assert_eq!(out, U256::from(1_974_316_068_794_122_597u64));
+
+        assert_eq!(get_amount_out(U256::zero(), e18(1), e18(1), 30), Err(SwapError::InsufficientInputAmount));
+        assert_eq!(get_amount_out(e18(1), U256::zero(), e18(1), 30), Err(SwapError::InsufficientLiquidity));
+        assert_eq!(get_amount_out(e18(1), e18(1), e18(1), 10_000), Err(SwapError::InvalidFee(10_000)));
+        // Tiny inputs round to nothing rather than failing
+        assert_eq!(get_amount_out(U256::one(), e18(100), U256::from(100), 30).unwrap(), U256::zero());
+    }
+
+    #[test]
+    fn test_simulate_route_tracks_amounts_and_impact() {
+        let usdc = Token::new("USDC", "USD Coin", 6, address(1));
+        let metis = Token::new("METIS", "Metis Token", 18, address(2));
+        let leg = |pool: u8, fee_bps: u32, reserve_usdc: u64, reserve_metis: u64, buy: bool| {
+            let exchange = Exchange::new("netswap", METIS_CHAIN_ID);
+            let pool = Pool::new(address(pool), exchange, metis.clone(), usdc.clone()).with_fee_bps(fee_bps);
+            let usdc_reserve = usdc.amount(U256::from(reserve_usdc) * U256::exp10(6));
+            let metis_reserve = metis.amount(e18(reserve_metis));
+            let price = Decimal::from(reserve_usdc) / Decimal::from(reserve_metis);
+            if buy {
+                ArbitrageLeg::new(usdc.clone(), metis.clone(), pool, Decimal::ONE / price, dec!(1))
+                    .with_reserves(usdc_reserve, metis_reserve)
+            } else {
+                ArbitrageLeg::new(metis.clone(), usdc.clone(), pool, price, dec!(1))
+                    .with_reserves(metis_reserve, usdc_reserve)
+            }
+        };
+
+        // Buy METIS at 84 on one pool, sell at 85 on another
+        let route = ArbitrageRoute::new(vec![
+            leg(0xa1, 20, 168_000, 2_000, true),
+            leg(0xa2, 30, 170_000, 2_000, false),
+        ])
+        .unwrap();
+
+        let small = simulate_route(&route, usdc.parse_amount(dec!(100)).unwrap()).unwrap();
+        assert!(small.is_profitable());
+        assert_eq!(small.legs[0].mid_price.round_dp(6), dec!(0.011905));
+        assert!(small.max_price_impact_percent() < dec!(0.1));
+        assert_eq!(small.legs[1].amount_in, small.legs[0].amount_out);
+
+        // Size eats the edge: impact grows past the 0.7% spread
+        let large = simulate_route(&route, usdc.parse_amount(dec!(10000)).unwrap()).unwrap();
+        assert!(!large.is_profitable());
+        assert!(large.legs[0].price_impact_percent > dec!(5));
+
+        let opportunity = small.to_opportunity(route.clone(), Decimal::ZERO).unwrap();
+        assert_eq!(opportunity.input_amount, dec!(100));
+        assert_eq!(opportunity.output_amount, small.amount_out().to_decimal().unwrap());
+        assert_eq!(opportunity.exact_input(), Some(small.amount_in()));
+        assert_eq!(opportunity.exact_output(), Some(small.amount_out()));
+        assert_eq!(opportunity.swap_amounts.as_ref().unwrap()[1], small.legs[0].amount_out);
+
+        assert_eq!(
+            simulate_route(&route, metis.parse_amount(dec!(1)).unwrap()),
+            Err(SimulationError::Amount(AmountError::DecimalsMismatch(18, 6)))
+        );
+        // Reserves too precise for a Decimal still simulate, prices rounded
+        let mut deep = route.legs().to_vec();
+        let whale = metis.amount(e18(100_000_000_000) + U256::one());
+        deep[0].reserves = Some((usdc.amount(U256::from(8_400_000_000_000u64) * U256::exp10(6)), whale));
+        let deep = simulate_route(&ArbitrageRoute::new(deep).unwrap(), small.amount_in()).unwrap();
+        assert_eq!(deep.legs[0].mid_price.round_dp(6), dec!(0.011905));
+        assert!(deep.legs[0].price_impact_percent < small.legs[0].price_impact_percent);
+
+        let mut bare = route.legs().to_vec();
+        bare[1].reserves = None;
+        let bare = ArbitrageRoute::new(bare).unwrap();
+        assert_eq!(simulate_route(&bare, small.amount_in()), Err(SimulationError::MissingReserves(1)));
+    }
+}
@@ -0,0 +1,288 @@
+//! sizing.rs - Profit-maximizing trade sizes for arbitrage routes
+//!
+//! Profit along a constant-product route rises and then falls with
+//! the input as price impact eats the spread. Two-pool cycles compose into a
+//! single curve `out = K·x / (D + E·x)` whose optimum has a closed form;
+//! longer routes are searched numerically with golden-section search. Either
+//! way the input is capped by the configured capital limits and the chosen
+//! size is executed exactly through the route simulator.
+
+use primitive_types::U256;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use crate::address::Address;
+use crate::amount::{AmountError, TokenAmount};
+use crate::models::{ArbitrageLeg, ArbitrageOpportunity, ArbitrageRoute};
+use crate::simulation::{simulate_route, RouteSimulation, SimulationError, SwapError};
+
+/// Relative width at which the golden-section search stops
+const SEARCH_TOLERANCE: f64 = 1e-9;
+
+/// Upper bound on golden-section iterations
+const MAX_SEARCH_ITERATIONS: usize = 200;
+
+/// Errors raised while sizing a route
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum SizingError {
+    #[error("route is not profitable at any size")]
+    Unprofitable,
+
+    #[error(transparent)]
+    Simulation(#[from] SimulationError),
+
+    #[error(transparent)]
+    Amount(#[from] AmountError),
+}
+
+/// Most capital a single route may put in, per start token
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CapitalLimits {
+    /// Maximum input (in token units, e.g. 5000 USDC) keyed by token address
+    pub max_input: BTreeMap<Address, Decimal>,
+    /// Maximum input for tokens without an entry; None leaves them bounded
+    /// only by pool reserves
+    pub default_max_input: Option<Decimal>,
+}
+
+impl CapitalLimits {
+    pub fn with_max_input(mut self, token: Address, amount: Decimal) -> Self {
+        self.max_input.insert(token, amount);
+        self
+    }
+
+    pub fn with_default_max_input(mut self, amount: Decimal) -> Self {
+        self.default_max_input = Some(amount);
+        self
+    }
+
+    /// Cap on the input of routes starting in `token`
+    pub fn max_input(&self, token: Address) -> Option<Decimal> {
+        self.max_input.get(&token).copied().or(self.default_max_input)
+    }
+}
+
+/// The chosen input for a route and its simulated execution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizedTrade {
+    pub input: TokenAmount,
+    pub simulation: RouteSimulation,
+    /// Whether the capital limit, not the curve, determined the size
+    pub capped: bool,
+}
+
+/// Picks the profit-maximizing input for routes, within capital limits
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TradeSizer {
+    limits: CapitalLimits,
+}
+
+impl TradeSizer {
+    pub fn new(limits: CapitalLimits) -> Self {
+        TradeSizer { limits }
+    }
+
+    /// Profit-maximizing input for `route`, executed against its reserves
+    pub fn optimal_input(&self, route: &ArbitrageRoute) -> Result<SizedTrade, SizingError> {
+        let start = route.start_token();
+        let (first_reserve_in, _) = route.legs()[0].reserves.ok_or(SimulationError::MissingReserves(0))?;
+
+        // Putting in more than the first pool holds is never optimal
+        let mut upper = to_f64(first_reserve_in.raw());
+        let mut cap = None;
+        if let Some(max) = self.limits.max_input(start.address) {
+            let max = TokenAmount::from_decimal_floor(max, start.decimals)?;
+            if to_f64(max.raw()) < upper {
+                upper = to_f64(max.raw());
+                cap = Some(max);
+            }
+        }
+
+        let optimum = match route.legs() {
+            [first, second] => two_pool_optimum(first, second)?,
+            _ => golden_section_max(|x| profit(route, x), upper),
+        };
+
+        // A search bounded by the cap lands just short of it
+        let capped = cap.is_some() && optimum >= upper * (1.0 - 2.0 * SEARCH_TOLERANCE);
+        let input = match cap {
+            Some(cap) if capped => cap,
+            _ => start.amount(from_f64(optimum.min(upper))),
+        };
+        if input.is_zero() {
+            return Err(SizingError::Unprofitable);
+        }
+        let simulation = simulate_route(route, input)?;
+        if !simulation.is_profitable() {
+            return Err(SizingError::Unprofitable);
+        }
+        Ok(SizedTrade { input, simulation, capped })
+    }
+
+    /// Opportunity for `route` at its optimal size; `gas_cost` is in units
+    /// of the start token and is deducted from the net profit
+    pub fn size(&self, route: ArbitrageRoute, gas_cost: Decimal) -> Result<ArbitrageOpportunity, SizingError> {
+        let trade = self.optimal_input(&route)?;
+        Ok(trade.simulation.to_opportunity(route, gas_cost)?)
+    }
+}
+
+/// Closed-form optimum of a two-pool cycle
+///
+/// Swapping x through reserves (a1 → b1) and back through (b2 → a2) with
+/// fee multipliers g1, g2 returns K·x / (D + E·x), where K = g1·g2·b1·a2,
+/// D = a1·b2 and E = g1·(b2 + g2·b1). Profit peaks where the slope
+/// K·D / (D + E·x)² falls to 1, at x = (√(K·D) − D) / E.
+fn two_pool_optimum(first: &ArbitrageLeg, second: &ArbitrageLeg) -> Result<f64, SizingError> {
+    let (a1, b1) = first.reserves.ok_or(SimulationError::MissingReserves(0))?;
+    let (b2, a2) = second.reserves.ok_or(SimulationError::MissingReserves(1))?;
+    let multiplier = |leg: &ArbitrageLeg| (Decimal::ONE - leg.pool.fee_rate()).to_f64().unwrap_or(0.0);
+    let (g1, g2) = (multiplier(first), multiplier(second));
+    let (a1, b1, b2, a2) = (to_f64(a1.raw()), to_f64(b1.raw()), to_f64(b2.raw()), to_f64(a2.raw()));
+
+    let k = g1 * g2 * b1 * a2;
+    let d = a1 * b2;
+    let e = g1 * (b2 + g2 * b1);
+    if k <= d || e <= 0.0 {
+        return Err(SizingError::Unprofitable);
+    }
+    Ok((k.sqrt() * d.sqrt() - d) / e)
+}
+
+/// Simulated profit (in raw start-token units) of putting in `x`
+fn profit(route: &ArbitrageRoute, x: f64) -> f64 {
+    let input = route.start_token().amount(from_f64(x));
+    match simulate_route(route, input) {
+        Ok(simulation) => to_f64(simulation.amount_out().raw()) - to_f64(input.raw()),
+        Err(SimulationError::Swap { source: SwapError::InsufficientInputAmount, .. }) => 0.0,
+        Err(_) => f64::NEG_INFINITY,
+    }
+}
+
+/// Maximum of a unimodal `f` on [0, upper]
+fn golden_section_max(f: impl Fn(f64) -> f64, upper: f64) -> f64 {
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut lo, mut hi) = (0.0, upper);
+    let mut c = hi - ratio * (hi - lo);
+    let mut d = lo + ratio * (hi - lo);
+    let (mut fc, mut fd) = (f(c), f(d));
+
+    for _ in 0..MAX_SEARCH_ITERATIONS {
+        if hi - lo <= (hi * SEARCH_TOLERANCE).max(1.0) {
+            break;
+        }
+        if fc > fd {
+            hi = d;
+            (d, fd) = (c, fc);
+            c = hi - ratio * (hi - lo);
+            fc = f(c);
+        } else {
+            lo = c;
+            (c, fc) = (d, fd);
+            d = lo + ratio * (hi - lo);
+            fd = f(d);
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+fn to_f64(value: U256) -> f64 {
+    value.to_string().parse().unwrap_or(f64::INFINITY)
+}
+
+/// Round a non-negative size down to whole base units
+fn from_f64(value: f64) -> U256 {
+    if value.is_nan() || value < 1.0 {
+        return U256::zero();
+    }
+    U256::from_dec_str(&format!("{:.0}", value.floor())).unwrap_or(U256::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::METIS_CHAIN_ID;
+    use crate::models::{Exchange, Pool, Token};
+    use crate::test_util::{address, token};
+    use rust_decimal_macros::dec;
+
+    /// Leg through a fresh 0.3% pool holding `reserve_in` / `reserve_out` whole tokens
+    fn leg(from: &Token, to: &Token, pool: u8, reserve_in: u64, reserve_out: u64) -> ArbitrageLeg {
+        let exchange = Exchange::new("netswap", METIS_CHAIN_ID);
+        let pool = Pool::new(address(pool), exchange, from.clone(), to.clone());
+        let price = Decimal::from(reserve_out) / Decimal::from(reserve_in);
+        ArbitrageLeg::new(from.clone(), to.clone(), pool, price, dec!(1))
+            .with_reserves(from.parse_amount(reserve_in.into()).unwrap(), to.parse_amount(reserve_out.into()).unwrap())
+    }
+
+    fn e18(n: u64) -> U256 {
+        U256::from(n) * U256::exp10(18)
+    }
+
+    fn profit_at(route: &ArbitrageRoute, input: U256) -> f64 {
+        profit(route, to_f64(input))
+    }
+
+    #[test]
+    fn test_two_pool_closed_form_is_optimal() {
+        let (usdc, metis) = (token("USDC", 1), token("METIS", 2));
+        // Buy METIS at 84 USDC, sell at 86
+        let route = ArbitrageRoute::new(vec![
+            leg(&usdc, &metis, 0xa1, 168_000, 2_000),
+            leg(&metis, &usdc, 0xa2, 2_000, 172_000),
+        ])
+        .unwrap();
+
+        let trade = TradeSizer::default().optimal_input(&route).unwrap();
+        assert!(!trade.capped);
+        let best = profit_at(&route, trade.input.raw());
+        assert!(best > 0.0);
+        for factor in [90u64, 99, 101, 110] {
+            let nearby = trade.input.raw() * U256::from(factor) / U256::from(100);
+            assert!(profit_at(&route, nearby) < best);
+        }
+
+        // Agrees with the numeric search to well under 0.1%
+        let searched = golden_section_max(|x| profit(&route, x), to_f64(e18(168_000)));
+        let closed = to_f64(trade.input.raw());
+        assert!((searched - closed).abs() / closed < 1e-3);
+
+        let opportunity = TradeSizer::default().size(route, dec!(1)).unwrap();
+        assert_eq!(opportunity.input_amount, trade.input.to_decimal().unwrap());
+        assert_eq!(opportunity.net_profit, opportunity.gross_profit - dec!(1));
+    }
+
+    #[test]
+    fn test_longer_routes_and_capital_caps() {
+        let (usdc, weth, metis) = (token("USDC", 1), token("WETH", 2), token("METIS", 3));
+        let route = ArbitrageRoute::new(vec![
+            leg(&usdc, &weth, 0xa1, 2_000_000, 1_000),
+            leg(&weth, &metis, 0xa2, 1_000, 24_000),
+            leg(&metis, &usdc, 0xa3, 24_000, 2_040_000),
+        ])
+        .unwrap();
+
+        let trade = TradeSizer::default().optimal_input(&route).unwrap();
+        let best = profit_at(&route, trade.input.raw());
+        for factor in [95u64, 105] {
+            assert!(profit_at(&route, trade.input.raw() * U256::from(factor) / U256::from(100)) < best);
+        }
+
+        let limits = CapitalLimits::default().with_max_input(usdc.address, dec!(100));
+        let capped = TradeSizer::new(limits).optimal_input(&route).unwrap();
+        assert!(capped.capped);
+        assert_eq!(capped.input, usdc.parse_amount(dec!(100)).unwrap());
+
+        // No edge, no trade
+        let flat = ArbitrageRoute::new(vec![
+            leg(&usdc, &weth, 0xa1, 2_000_000, 1_000),
+            leg(&weth, &usdc, 0xa2, 1_000, 2_000_000),
+        ])
+        .unwrap();
+        assert_eq!(TradeSizer::default().optimal_input(&flat), Err(SizingError::Unprofitable));
+    }
+}
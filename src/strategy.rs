@@ -0,0 +1,429 @@
+//! strategy.rs - Pluggable arbitrage strategies
+//!
+//! An ArbitrageStrategy turns a market snapshot (pairs, filter and the time
+//! staleness is judged against) into ranked ArbitrageOpportunity values.
+//! Cross-DEX spreads, triangular cycles and two-pool cycles are built in;
+//! StrategyConfig selects and parameterizes them from the config file, and
+//! StrategyRegistry runs every selected strategy each scan, reporting
+//! per-strategy diagnostics alongside the opportunities.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::address::Address;
+use crate::cycles::{CycleDetector, DEFAULT_MIN_PROFIT_PERCENT};
+use crate::detection::{find_cross_dex_opportunities, DEFAULT_MIN_SPREAD_PERCENT};
+use crate::filter::PairFilter;
+use crate::models::{ArbitrageOpportunity, TradingPair};
+use crate::sizing::{CapitalLimits, TradeSizer};
+
+/// The pairs a scan sees and how to judge them
+#[derive(Debug, Clone, Copy)]
+pub struct MarketSnapshot<'a> {
+    pub pairs: &'a [TradingPair],
+    pub filter: &'a PairFilter,
+    /// Time staleness is judged against: the wall clock for live data, the
+    /// snapshot time for a replay
+    pub now: DateTime<Utc>,
+}
+
+impl<'a> MarketSnapshot<'a> {
+    pub fn new(pairs: &'a [TradingPair], filter: &'a PairFilter, now: DateTime<Utc>) -> Self {
+        MarketSnapshot { pairs, filter, now }
+    }
+}
+
+/// What a strategy saw while evaluating a snapshot
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StrategyDiagnostics {
+    /// Pairs passing the filter
+    pub pairs_considered: usize,
+    /// Pairs rejected by the filter, by exclusion kind
+    pub exclusions: BTreeMap<&'static str, usize>,
+    pub elapsed: Duration,
+}
+
+/// One strategy's result for a snapshot
+#[derive(Debug, Clone)]
+pub struct StrategyReport {
+    pub strategy: String,
+    /// Most profitable first
+    pub opportunities: Vec<ArbitrageOpportunity>,
+    pub diagnostics: StrategyDiagnostics,
+}
+
+/// A way of finding arbitrage opportunities in a market snapshot
+pub trait ArbitrageStrategy: Send + Sync {
+    /// Short name used in logs (e.g. "cross_dex")
+    fn name(&self) -> &str;
+
+    /// Opportunities in `market`, in any order
+    fn find_opportunities(&self, market: &MarketSnapshot<'_>) -> Vec<ArbitrageOpportunity>;
+
+    /// Opportunities ranked by profit percentage, with diagnostics
+    fn evaluate(&self, market: &MarketSnapshot<'_>) -> StrategyReport {
+        let started = Instant::now();
+        let mut opportunities = self.find_opportunities(market);
+        opportunities.sort_by_key(|o| std::cmp::Reverse(o.profit_percentage));
+
+        let mut diagnostics = StrategyDiagnostics::default();
+        for pair in market.pairs {
+            match market.filter.check_at(pair, market.now) {
+                Ok(()) => diagnostics.pairs_considered += 1,
+                Err(reason) => *diagnostics.exclusions.entry(reason.kind()).or_default() += 1,
+            }
+        }
+        diagnostics.elapsed = started.elapsed();
+
+        StrategyReport { strategy: self.name().to_string(), opportunities, diagnostics }
+    }
+}
+
+/// The same pair bought on one DEX and sold on another
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossDexStrategy {
+    min_spread_percent: Decimal,
+    input_amount: Decimal,
+}
+
+impl CrossDexStrategy {
+    /// Spreads above `DEFAULT_MIN_SPREAD_PERCENT`, priced for one unit of
+    /// the quote token
+    pub fn new() -> Self {
+        CrossDexStrategy {
+            min_spread_percent: DEFAULT_MIN_SPREAD_PERCENT,
+            input_amount: Decimal::ONE,
+        }
+    }
+
+    pub fn with_min_spread_percent(mut self, min_spread_percent: Decimal) -> Self {
+        self.min_spread_percent = min_spread_percent;
+        self
+    }
+
+    /// Amount of the quote token each spread is priced for
+    pub fn with_input_amount(mut self, input_amount: Decimal) -> Self {
+        self.input_amount = input_amount;
+        self
+    }
+}
+
+impl Default for CrossDexStrategy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArbitrageStrategy for CrossDexStrategy {
+    fn name(&self) -> &str {
+        "cross_dex"
+    }
+
+    fn find_opportunities(&self, market: &MarketSnapshot<'_>) -> Vec<ArbitrageOpportunity> {
+        find_cross_dex_opportunities(market.pairs, market.filter, market.now, self.min_spread_percent, self.input_amount)
+    }
+}
+
+/// Three-hop cycles through three distinct pools (e.g. USDC → WETH → METIS → USDC)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TriangularStrategy {
+    detector: CycleDetector,
+}
+
+impl TriangularStrategy {
+    /// Search with `detector`'s thresholds, sizing and start tokens; its
+    /// hop limit is replaced by three
+    pub fn new(detector: CycleDetector) -> Self {
+        TriangularStrategy { detector: detector.with_max_hops(3) }
+    }
+}
+
+impl Default for TriangularStrategy {
+    fn default() -> Self {
+        Self::new(CycleDetector::new())
+    }
+}
+
+impl ArbitrageStrategy for TriangularStrategy {
+    fn name(&self) -> &str {
+        "triangular"
+    }
+
+    fn find_opportunities(&self, market: &MarketSnapshot<'_>) -> Vec<ArbitrageOpportunity> {
+        self.detector.find_cycles(market.pairs, market.filter, market.now)
+            .into_iter()
+            .filter(|o| o.route.total_hops() == 3)
+            .collect()
+    }
+}
+
+/// Two-hop cycles between two pools of the same pair, executed against
+/// reserves (and sized in closed form when a sizer is set)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TwoPoolStrategy {
+    detector: CycleDetector,
+}
+
+impl TwoPoolStrategy {
+    /// Search with `detector`'s thresholds, sizing and start tokens; its
+    /// hop limit is replaced by two
+    pub fn new(detector: CycleDetector) -> Self {
+        TwoPoolStrategy { detector: detector.with_max_hops(2) }
+    }
+}
+
+impl Default for TwoPoolStrategy {
+    fn default() -> Self {
+        Self::new(CycleDetector::new())
+    }
+}
+
+impl ArbitrageStrategy for TwoPoolStrategy {
+    fn name(&self) -> &str {
+        "two_pool"
+    }
+
+    fn find_opportunities(&self, market: &MarketSnapshot<'_>) -> Vec<ArbitrageOpportunity> {
+        self.detector.find_cycles(market.pairs, market.filter, market.now)
+    }
+}
+
+/// A built-in strategy and its parameters, as written in the config file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StrategyConfig {
+    /// Cross-DEX spreads above `min_spread_percent` after fees, priced for
+    /// `input_amount` of the quote token
+    CrossDex {
+        #[serde(default = "default_min_spread_percent")]
+        min_spread_percent: Decimal,
+        #[serde(default = "default_input_amount")]
+        input_amount: Decimal,
+    },
+    /// Three-pool cycles from `start_tokens` (empty allows every token);
+    /// `sized` picks the profit-maximizing input within the capital limits
+    /// instead of pricing `input_amount`
+    Triangular {
+        #[serde(default = "default_min_profit_percent")]
+        min_profit_percent: Decimal,
+        #[serde(default = "default_input_amount")]
+        input_amount: Decimal,
+        #[serde(default)]
+        start_tokens: Vec<Address>,
+        #[serde(default = "default_sized")]
+        sized: bool,
+    },
+    /// Two-pool cycles, with the same parameters as `Triangular`
+    TwoPool {
+        #[serde(default = "default_min_profit_percent")]
+        min_profit_percent: Decimal,
+        #[serde(default = "default_input_amount")]
+        input_amount: Decimal,
+        #[serde(default)]
+        start_tokens: Vec<Address>,
+        #[serde(default = "default_sized")]
+        sized: bool,
+    },
+}
+
+fn default_min_spread_percent() -> Decimal {
+    DEFAULT_MIN_SPREAD_PERCENT
+}
+
+fn default_min_profit_percent() -> Decimal {
+    DEFAULT_MIN_PROFIT_PERCENT
+}
+
+fn default_input_amount() -> Decimal {
+    Decimal::ONE
+}
+
+fn default_sized() -> bool {
+    true
+}
+
+impl StrategyConfig {
+    /// Strategies run when the config names none: cross-DEX spreads and
+    /// sized two-pool and triangular cycles, all with default parameters
+    pub fn defaults() -> Vec<StrategyConfig> {
+        vec![
+            StrategyConfig::CrossDex {
+                min_spread_percent: default_min_spread_percent(),
+                input_amount: default_input_amount(),
+            },
+            StrategyConfig::TwoPool {
+                min_profit_percent: default_min_profit_percent(),
+                input_amount: default_input_amount(),
+                start_tokens: Vec::new(),
+                sized: default_sized(),
+            },
+            StrategyConfig::Triangular {
+                min_profit_percent: default_min_profit_percent(),
+                input_amount: default_input_amount(),
+                start_tokens: Vec::new(),
+                sized: default_sized(),
+            },
+        ]
+    }
+
+    /// The configured strategy; sized cycle strategies are capped by `capital`
+    pub fn build(&self, capital: &CapitalLimits) -> Box<dyn ArbitrageStrategy> {
+        let detector = |min_profit_percent: Decimal, input_amount: Decimal, start_tokens: &[Address], sized: bool| {
+            let detector = CycleDetector::new()
+                .with_min_profit_percent(min_profit_percent)
+                .with_input_amount(input_amount)
+                .with_start_tokens(start_tokens.to_vec());
+            if sized {
+                detector.with_sizer(TradeSizer::new(capital.clone()))
+            } else {
+                detector
+            }
+        };
+
+        match self {
+            StrategyConfig::CrossDex { min_spread_percent, input_amount } => Box::new(
+                CrossDexStrategy::new()
+                    .with_min_spread_percent(*min_spread_percent)
+                    .with_input_amount(*input_amount),
+            ),
+            StrategyConfig::Triangular { min_profit_percent, input_amount, start_tokens, sized } => Box::new(
+                TriangularStrategy::new(detector(*min_profit_percent, *input_amount, start_tokens, *sized)),
+            ),
+            StrategyConfig::TwoPool { min_profit_percent, input_amount, start_tokens, sized } => Box::new(
+                TwoPoolStrategy::new(detector(*min_profit_percent, *input_amount, start_tokens, *sized)),
+            ),
+        }
+    }
+}
+
+/// The strategies a scan runs, in order
+#[derive(Default)]
+pub struct StrategyRegistry {
+    strategies: Vec<Box<dyn ArbitrageStrategy>>,
+}
+
+impl StrategyRegistry {
+    /// An empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Built-in strategies as configured, or `StrategyConfig::defaults()`
+    /// if `configs` is empty
+    pub fn from_config(configs: &[StrategyConfig], capital: &CapitalLimits) -> Self {
+        let defaults;
+        let configs = if configs.is_empty() {
+            defaults = StrategyConfig::defaults();
+            &defaults
+        } else {
+            configs
+        };
+        StrategyRegistry {
+            strategies: configs.iter().map(|c| c.build(capital)).collect(),
+        }
+    }
+
+    /// Add a strategy, run after those already registered
+    pub fn with_strategy(mut self, strategy: impl ArbitrageStrategy + 'static) -> Self {
+        self.strategies.push(Box::new(strategy));
+        self
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.strategies.iter().map(|s| s.name()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.strategies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strategies.is_empty()
+    }
+
+    /// Evaluate every strategy against `market`, in registration order
+    pub fn run(&self, market: &MarketSnapshot<'_>) -> Vec<StrategyReport> {
+        self.strategies.iter().map(|s| s.evaluate(market)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_feed::{MockPriceFeed, PriceFeed};
+    use rust_decimal_macros::dec;
+
+    /// Stands in for a strategy defined outside the built-in set
+    struct Nothing;
+
+    impl ArbitrageStrategy for Nothing {
+        fn name(&self) -> &str {
+            "nothing"
+        }
+
+        fn find_opportunities(&self, _market: &MarketSnapshot<'_>) -> Vec<ArbitrageOpportunity> {
+            Vec::new()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_registry_runs_built_in_strategies() {
+        let pairs = MockPriceFeed::new().get_trading_pairs().await;
+        let filter = PairFilter::default().with_min_liquidity(dec!(200000));
+        let market = MarketSnapshot::new(&pairs, &filter, Utc::now());
+
+        let registry = StrategyRegistry::from_config(&[], &CapitalLimits::default()).with_strategy(Nothing);
+        assert_eq!(registry.names(), vec!["cross_dex", "two_pool", "triangular", "nothing"]);
+
+        // The 150k Tethys METIS pool is filtered out, so nothing is found
+        let reports = registry.run(&market);
+        assert_eq!(reports.len(), 4);
+        assert!(reports.iter().all(|r| r.opportunities.is_empty()));
+        assert_eq!(reports[0].diagnostics.pairs_considered, 3);
+        assert_eq!(reports[0].diagnostics.exclusions.get("low_liquidity"), Some(&1));
+
+        let filter = PairFilter::default();
+        let reports = registry.run(&MarketSnapshot::new(&pairs, &filter, Utc::now()));
+        let found: Vec<(&str, usize)> = reports.iter().map(|r| (r.strategy.as_str(), r.opportunities.len())).collect();
+        assert_eq!(found, vec![("cross_dex", 1), ("two_pool", 1), ("triangular", 0), ("nothing", 0)]);
+        assert_eq!(reports[1].opportunities[0].route.total_hops(), 2);
+        // Sized for maximum profit rather than priced for one unit
+        assert!(reports[1].opportunities[0].input_amount > Decimal::ONE);
+    }
+
+    #[tokio::test]
+    async fn test_strategies_from_config() {
+        let configs: Vec<StrategyConfig> = serde_json::from_str(r#"[
+            { "type": "cross_dex", "min_spread_percent": "2", "input_amount": "1000" },
+            {
+                "type": "two_pool", "sized": false, "input_amount": "10",
+                "start_tokens": ["0xEA32A96608495e54156Ae48931A7c20f0dcc1a21"]
+            }
+        ]"#).unwrap();
+        let usdc: Address = "0xEA32A96608495e54156Ae48931A7c20f0dcc1a21".parse().unwrap();
+        assert_eq!(
+            configs[1],
+            StrategyConfig::TwoPool {
+                min_profit_percent: DEFAULT_MIN_PROFIT_PERCENT,
+                input_amount: dec!(10),
+                start_tokens: vec![usdc],
+                sized: false,
+            }
+        );
+
+        let registry = StrategyRegistry::from_config(&configs, &CapitalLimits::default());
+        assert_eq!(registry.names(), vec!["cross_dex", "two_pool"]);
+
+        let pairs = MockPriceFeed::new().get_trading_pairs().await;
+        let filter = PairFilter::default();
+        let reports = registry.run(&MarketSnapshot::new(&pairs, &filter, Utc::now()));
+        // The ~0.69% METIS spread is below the configured 2%
+        assert!(reports[0].opportunities.is_empty());
+        // Ten USDC, not ten METIS, which would move the pools too far
+        assert_eq!(reports[1].opportunities[0].input_amount, dec!(10));
+        assert_eq!(reports[1].opportunities[0].route.start_token().address, usdc);
+    }
+}
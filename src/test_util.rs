@@ -0,0 +1,243 @@
+//! test_util.rs - Local stand-ins for external services used in tests
+//!
+//! Provides a tiny HTTP/1.1 server and a JSON-RPC server built on top of it,
+//! so feeds can be exercised without network access, plus placeholder
+//! addresses and tokens shared by the unit tests.
+
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use crate::address::Address;
+use crate::models::Token;
+
+/// Placeholder address ending in `byte`
+pub fn address(byte: u8) -> Address {
+    let mut bytes = [0u8; 20];
+    bytes[19] = byte;
+    Address::new(bytes)
+}
+
+/// 18-decimal token named `symbol` at `address(byte)`
+pub fn token(symbol: &str, byte: u8) -> Token {
+    Token::new(symbol, symbol, 18, address(byte))
+}
+
+/// Request received by the mock HTTP server
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub path: String,
+    pub body: String,
+}
+
+/// Response returned by a mock handler
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl MockResponse {
+    pub fn json(body: Value) -> Self {
+        MockResponse { status: 200, headers: Vec::new(), body: body.to_string() }
+    }
+
+    pub fn status(status: u16) -> Self {
+        MockResponse { status, headers: Vec::new(), body: String::new() }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Minimal HTTP server answering every request through a handler closure
+pub struct MockHttpServer {
+    pub url: String,
+    handle: JoinHandle<()>,
+}
+
+impl MockHttpServer {
+    pub async fn start<F>(handler: F) -> Self
+    where
+        F: Fn(MockRequest) -> MockResponse + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind mock server");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handler = Arc::new(handler);
+
+        let handle = tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else { break };
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    let _ = serve_connection(stream, handler.as_ref()).await;
+                });
+            }
+        });
+
+        MockHttpServer { url, handle }
+    }
+}
+
+impl Drop for MockHttpServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn serve_connection<F>(mut stream: TcpStream, handler: &F) -> std::io::Result<()>
+where
+    F: Fn(MockRequest) -> MockResponse,
+{
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    let header_end = loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).into_owned();
+    let content_length = head
+        .lines()
+        .filter_map(|l| l.split_once(':'))
+        .find(|(k, _)| k.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, v)| v.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+
+    while buf.len() < header_end + content_length {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    let path = head.split_whitespace().nth(1).unwrap_or("/").to_string();
+    let body = String::from_utf8_lossy(&buf[header_end..]).into_owned();
+    let response = handler(MockRequest { path, body });
+
+    let mut out = format!(
+        "HTTP/1.1 {} Mock\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n",
+        response.status,
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        out.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    out.push_str("\r\n");
+    out.push_str(&response.body);
+
+    stream.write_all(out.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// JSON-RPC server answering each call through `handler(method, params)`
+pub struct MockRpcServer {
+    pub url: String,
+    _http: MockHttpServer,
+}
+
+impl MockRpcServer {
+    pub async fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&str, &Value) -> Result<Value, (i64, String)> + Send + Sync + 'static,
+    {
+        let http = MockHttpServer::start(move |req| {
+            let request: Value = serde_json::from_str(&req.body).unwrap_or(Value::Null);
+            let id = request["id"].clone();
+            let method = request["method"].as_str().unwrap_or_default();
+
+            let body = match handler(method, &request["params"]) {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                Err((code, message)) => json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": code, "message": message },
+                }),
+            };
+            MockResponse::json(body)
+        })
+        .await;
+
+        MockRpcServer { url: http.url.clone(), _http: http }
+    }
+}
+
+/// Execute an `aggregate3` calldata payload against `call(target, data)`,
+/// returning the ABI-encoded `(bool, bytes)[]` result. `None` means revert.
+pub fn serve_aggregate3<F>(calldata: &[u8], call: F) -> Vec<u8>
+where
+    F: Fn(&str, &[u8]) -> Option<Vec<u8>>,
+{
+    use crate::abi::{self, WORD_SIZE};
+
+    let read = |data: &[u8], at: usize| abi::decode_u128(&data[at..at + WORD_SIZE]).unwrap() as usize;
+
+    // Skip the selector; the array starts after the head offset word
+    let input = &calldata[4..];
+    let array = &input[read(input, 0)..];
+    let len = read(array, 0);
+    let items = &array[WORD_SIZE..];
+
+    let results: Vec<(bool, Vec<u8>)> = (0..len)
+        .map(|i| {
+            let tuple = &items[read(items, i * WORD_SIZE)..];
+            let target = abi::decode_address(&tuple[..WORD_SIZE]).unwrap();
+            let bytes_at = read(tuple, 2 * WORD_SIZE);
+            let bytes_len = read(tuple, bytes_at);
+            let data = &tuple[bytes_at + WORD_SIZE..bytes_at + WORD_SIZE + bytes_len];
+            match call(&target, data) {
+                Some(out) => (true, out),
+                None => (false, Vec::new()),
+            }
+        })
+        .collect();
+
+    let mut tuples = Vec::new();
+    for (success, data) in &results {
+        let mut tuple = Vec::new();
+        tuple.extend_from_slice(&abi::encode_u128(u128::from(*success)));
+        tuple.extend_from_slice(&abi::encode_u128(2 * WORD_SIZE as u128));
+        tuple.extend_from_slice(&abi::encode_u128(data.len() as u128));
+        tuple.extend_from_slice(data);
+        tuple.resize(3 * WORD_SIZE + data.len().div_ceil(WORD_SIZE) * WORD_SIZE, 0);
+        tuples.push(tuple);
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&abi::encode_u128(WORD_SIZE as u128));
+    out.extend_from_slice(&abi::encode_u128(results.len() as u128));
+    let mut offset = results.len() * WORD_SIZE;
+    for tuple in &tuples {
+        out.extend_from_slice(&abi::encode_u128(offset as u128));
+        offset += tuple.len();
+    }
+    for tuple in tuples {
+        out.extend_from_slice(&tuple);
+    }
+    out
+}
+
+/// ABI-encode a dynamic `string` return value
+pub fn abi_string(s: &str) -> Vec<u8> {
+    use crate::abi;
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&abi::encode_u128(32));
+    out.extend_from_slice(&abi::encode_u128(s.len() as u128));
+    out.extend_from_slice(s.as_bytes());
+    out.resize(64 + s.len().div_ceil(32) * 32, 0);
+    out
+}
@@ -0,0 +1,309 @@
+//! token_list.rs - Verified token registry and spoofed-token detection
+//!
+//! Tokens are canonical by address. The registry imports the
+//! standard Uniswap token-list JSON format, classifies tokens seen in pools
+//! as verified, spoofed (a verified symbol at another address) or unverified,
+//! and flags symbols that map to several addresses. Symbols are compared
+//! after normalization, so bridge prefixes like `m.` and configured aliases
+//! cannot hide a spoof.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::address::Address;
+use crate::chain::METIS_CHAIN_ID;
+use crate::models::{Token, TradingPair};
+use crate::token_registry::known_metis_tokens;
+
+/// Errors raised while importing a token list
+#[derive(Debug, Error)]
+pub enum TokenListError {
+    #[error("failed to read token list {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("invalid token list: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// A token list in the Uniswap token-list format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenList {
+    pub name: String,
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    pub tokens: Vec<TokenListEntry>,
+}
+
+/// One entry of a token list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenListEntry {
+    pub chain_id: u64,
+    pub address: Address,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    #[serde(default, rename = "logoURI")]
+    pub logo_uri: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// How a token seen in a pool relates to the verified registry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenStatus {
+    /// The address is on the verified list
+    Verified,
+    /// The symbol belongs to a verified token at `canonical`
+    Spoofed { canonical: Address },
+    /// Neither the address nor the symbol is known
+    Unverified,
+}
+
+/// What filters do with tokens missing from the verified registry. Spoofed
+/// tokens are always quarantined once a registry is attached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnverifiedTokenPolicy {
+    /// Keep pairs with unverified tokens
+    #[default]
+    Allow,
+    /// Hold pairs with unverified tokens out of feeds and detection
+    Quarantine,
+}
+
+/// A symbol used by more than one token address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolCollision {
+    pub symbol: String,
+    pub addresses: Vec<Address>,
+}
+
+/// Bridge markers stripped from symbols before they are compared
+const BRIDGE_PREFIXES: &[&str] = &["M."];
+const BRIDGE_SUFFIXES: &[&str] = &[".E"];
+
+/// Uppercase `symbol` without bridge markers: "m.USDC" and "USDC.e" both
+/// normalize to "USDC"
+pub fn normalize_symbol(symbol: &str) -> String {
+    let mut symbol = symbol.trim().to_uppercase();
+    if let Some(rest) = BRIDGE_PREFIXES.iter().find_map(|p| symbol.strip_prefix(p)) {
+        symbol = rest.to_string();
+    }
+    if let Some(rest) = BRIDGE_SUFFIXES.iter().find_map(|s| symbol.strip_suffix(s)) {
+        symbol = rest.to_string();
+    }
+    symbol
+}
+
+/// Registry of canonical tokens for one chain, keyed by address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedTokens {
+    chain_id: u64,
+    tokens: HashMap<Address, Token>,
+    // Normalized symbol -> canonical address
+    by_symbol: HashMap<String, Address>,
+    // Normalized alias -> normalized symbol it stands for
+    aliases: HashMap<String, String>,
+}
+
+impl VerifiedTokens {
+    /// Create an empty registry for `chain_id`
+    pub fn new(chain_id: u64) -> Self {
+        VerifiedTokens {
+            chain_id,
+            tokens: HashMap::new(),
+            by_symbol: HashMap::new(),
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Registry seeded with the well-known Metis Andromeda tokens
+    pub fn metis() -> Self {
+        let mut verified = Self::new(METIS_CHAIN_ID);
+        for token in known_metis_tokens() {
+            verified.add(token);
+        }
+        verified
+    }
+
+    /// Import the entries of `list` that belong to this registry's chain
+    pub fn with_token_list(mut self, list: &TokenList) -> Self {
+        let chain_id = self.chain_id;
+        for entry in list.tokens.iter().filter(|t| t.chain_id == chain_id) {
+            let token = Token::new(&entry.symbol, &entry.name, entry.decimals, entry.address)
+                .with_chain_id(entry.chain_id);
+            self.add(token);
+        }
+        self
+    }
+
+    /// Treat `alias` as another name for `symbol`, e.g. "USDT" for "USDT0"
+    pub fn with_alias(mut self, alias: &str, symbol: &str) -> Self {
+        self.aliases.insert(normalize_symbol(alias), normalize_symbol(symbol));
+        self
+    }
+
+    /// Import a token-list JSON file
+    pub fn with_token_list_file(self, path: impl AsRef<Path>) -> Result<Self, TokenListError> {
+        let path = path.as_ref();
+        let raw = std::fs::read_to_string(path)
+            .map_err(|source| TokenListError::Io { path: path.to_path_buf(), source })?;
+        let list: TokenList = serde_json::from_str(&raw)?;
+        Ok(self.with_token_list(&list))
+    }
+
+    /// Mark a token as canonical. The first token registered for a symbol
+    /// stays its canonical address.
+    pub fn add(&mut self, token: Token) {
+        self.by_symbol.entry(normalize_symbol(&token.symbol)).or_insert(token.address);
+        self.tokens.insert(token.address, token);
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    pub fn is_verified(&self, address: Address) -> bool {
+        self.tokens.contains_key(&address)
+    }
+
+    /// Canonical token for a symbol, after normalization and aliases
+    pub fn canonical(&self, symbol: &str) -> Option<&Token> {
+        let symbol = normalize_symbol(symbol);
+        let symbol = self.aliases.get(&symbol).unwrap_or(&symbol);
+        self.by_symbol
+            .get(symbol)
+            .and_then(|address| self.tokens.get(address))
+    }
+
+    /// Classify a token seen in a pool. Tokens on another chain are
+    /// unverified: the registry says nothing about them.
+    pub fn classify(&self, token: &Token) -> TokenStatus {
+        if token.chain_id != self.chain_id {
+            return TokenStatus::Unverified;
+        }
+        if self.is_verified(token.address) {
+            return TokenStatus::Verified;
+        }
+        match self.canonical(&token.symbol) {
+            Some(canonical) => TokenStatus::Spoofed { canonical: canonical.address },
+            None => TokenStatus::Unverified,
+        }
+    }
+
+    /// Normalized symbols that several distinct addresses use across `pairs`
+    pub fn symbol_collisions(pairs: &[TradingPair]) -> Vec<SymbolCollision> {
+        let mut by_symbol: BTreeMap<String, BTreeSet<Address>> = BTreeMap::new();
+        for token in pairs.iter().flat_map(|p| [&p.base_token, &p.quote_token]) {
+            by_symbol.entry(normalize_symbol(&token.symbol)).or_default().insert(token.address);
+        }
+
+        by_symbol
+            .into_iter()
+            .filter(|(_, addresses)| addresses.len() > 1)
+            .map(|(symbol, addresses)| SymbolCollision { symbol, addresses: addresses.into_iter().collect() })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_feed::{MockPriceFeed, PriceFeed};
+
+    const FAKE_USDC: &str = "0x00000000000000000000000000000000000bad01";
+
+    #[test]
+    fn test_import_uniswap_token_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tokens.json");
+        std::fs::write(&path, r#"{
+            "name": "Metis Default",
+            "timestamp": "2024-01-01T00:00:00Z",
+            "version": { "major": 1, "minor": 0, "patch": 0 },
+            "tokens": [
+                { "chainId": 1088, "address": "0x0000000000000000000000000000000000000a11",
+                  "name": "Hercules", "symbol": "HER", "decimals": 18, "logoURI": "ipfs://x" },
+                { "chainId": 1, "address": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+                  "name": "USD Coin", "symbol": "USDC", "decimals": 6 }
+            ]
+        }"#).unwrap();
+
+        let verified = VerifiedTokens::metis().with_token_list_file(&path).unwrap();
+        assert_eq!(verified.len(), 5);
+        assert_eq!(verified.canonical("HER").unwrap().chain_id, 1088);
+        assert!(verified.is_verified("0x0000000000000000000000000000000000000A11".parse().unwrap()));
+        assert!(!verified.is_verified("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".parse().unwrap()));
+        assert_eq!(verified.canonical("her").unwrap().decimals, 18);
+    }
+
+    #[tokio::test]
+    async fn test_classify_and_flag_collisions() {
+        let verified = VerifiedTokens::metis();
+        let mut pairs = MockPriceFeed::new().get_trading_pairs().await;
+
+        let real = &pairs[0].quote_token;
+        assert_eq!(verified.classify(real), TokenStatus::Verified);
+
+        // A scam token reusing the canonical m.USDC symbol
+        let fake_usdc: Address = FAKE_USDC.parse().unwrap();
+        let fake = Token::new("m.USDC", "USD Coin", 6, fake_usdc);
+        assert_eq!(
+            verified.classify(&fake),
+            TokenStatus::Spoofed { canonical: "0xEA32A96608495e54156Ae48931A7c20f0dcc1a21".parse().unwrap() }
+        );
+        assert_eq!(verified.classify(&Token::new("HER", "Hercules", 18, fake_usdc)), TokenStatus::Unverified);
+
+        // Dropping the bridge prefix does not make the symbol unknown
+        let bare = Token::new("USDC", "USD Coin", 6, fake_usdc);
+        assert!(matches!(verified.classify(&bare), TokenStatus::Spoofed { .. }));
+        assert!(matches!(verified.classify(&Token::new("usdc.e", "USD Coin", 6, fake_usdc)), TokenStatus::Spoofed { .. }));
+
+        // Aliases map other names onto a canonical symbol
+        let tether = Token::new("USD₮", "Tether", 6, fake_usdc);
+        assert_eq!(verified.classify(&tether), TokenStatus::Unverified);
+        let aliased = verified.clone().with_alias("USD₮", "m.USDT");
+        assert!(matches!(aliased.classify(&tether), TokenStatus::Spoofed { .. }));
+
+        assert!(VerifiedTokens::symbol_collisions(&pairs).is_empty());
+        pairs[1].quote_token = Token::new(&pairs[0].quote_token.symbol, "Fake", 6, fake_usdc);
+        let collisions = VerifiedTokens::symbol_collisions(&pairs);
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].addresses.len(), 2);
+
+        // "USDC" next to "m.USDC" is the same symbol
+        pairs[1].quote_token = Token::new("USDC", "USD Coin", 6, fake_usdc);
+        let collisions = VerifiedTokens::symbol_collisions(&pairs);
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].symbol, "USDC");
+    }
+
+    #[test]
+    fn test_classify_token_on_another_chain() {
+        let verified = VerifiedTokens::metis();
+
+        // Arbitrum's USDC reuses the symbol but is no spoof of Metis m.USDC
+        let arbitrum_usdc = Token::new("USDC", "USD Coin", 6, FAKE_USDC.parse().unwrap()).with_chain_id(42161);
+        assert_eq!(verified.classify(&arbitrum_usdc), TokenStatus::Unverified);
+
+        // Nor does a Metis address count as verified off Metis
+        let real = verified.canonical("m.USDC").unwrap().clone();
+        assert_eq!(verified.classify(&real.clone().with_chain_id(42161)), TokenStatus::Unverified);
+        assert_eq!(verified.classify(&real), TokenStatus::Verified);
+    }
+}
@@ -0,0 +1,501 @@
+//! token_registry.rs - ERC-20 metadata resolution and caching
+//!
+//! Resolves decimals(), symbol() and name() for token addresses via
+//! JSON-RPC, caching results in memory by (chain id, address) and optionally
+//! on disk, so metadata is fetched only once per token. Bulk lookups can be
+//! batched through Multicall3; newly resolved tokens are written to disk once
+//! per batch or refresh rather than per lookup.
+
+use log::{debug, info, warn};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+use crate::abi::{self, AbiError};
+use crate::address::Address;
+use crate::chain::METIS_CHAIN_ID;
+use crate::models::Token;
+use crate::multicall::{Call, Multicall};
+use crate::rpc::{BlockTag, RpcClient, RpcError, METIS_RPC_URL};
+
+/// Default location of the on-disk token cache
+pub const DEFAULT_TOKEN_CACHE_PATH: &str = "token_cache.json";
+
+/// Errors raised while resolving or persisting token metadata
+#[derive(Debug, Error)]
+pub enum TokenRegistryError {
+    #[error("rpc error resolving {address}: {source}")]
+    Rpc { address: Address, source: RpcError },
+
+    #[error("invalid metadata for {address}: {source}")]
+    Abi { address: Address, source: AbiError },
+
+    #[error("token cache I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("token cache is not valid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Well-known Metis Andromeda tokens, used to seed the registry
+pub fn known_metis_tokens() -> Vec<Token> {
+    let token = |symbol: &str, name: &str, decimals: u8, address: &str| {
+        Token::new(symbol, name, decimals, address.parse().expect("known token address is checksummed"))
+    };
+    vec![
+        token("METIS", "Metis Token", 18, "0xDeadDeAddeAddEAddeadDEaDDEAdDeaDDeAD0000"),
+        token("WETH", "Wrapped Ether", 18, "0x420000000000000000000000000000000000000A"),
+        token("m.USDC", "USD Coin", 6, "0xEA32A96608495e54156Ae48931A7c20f0dcc1a21"),
+        token("m.USDT", "Tether USD", 6, "0xbB06DCA3AE6887fAbF931640f67cab3e3a16F4dC"),
+    ]
+}
+
+/// Cache key: the same address can hold different tokens on different chains
+type TokenKey = (u64, Address);
+
+/// On-disk cache layout; older caches were a map keyed by address
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredTokens {
+    List(Vec<Token>),
+    ByAddress(HashMap<Address, Token>),
+}
+
+impl StoredTokens {
+    fn into_tokens(self) -> Vec<Token> {
+        match self {
+            StoredTokens::List(tokens) => tokens,
+            StoredTokens::ByAddress(tokens) => tokens.into_values().collect(),
+        }
+    }
+}
+
+/// Tokens stored in a cache file, or none if it does not exist yet
+fn load_cache_file(path: &Path) -> Result<HashMap<TokenKey, Token>, TokenRegistryError> {
+    let mut tokens = HashMap::new();
+    if path.exists() {
+        let content = std::fs::read_to_string(path)?;
+        let stored: StoredTokens = serde_json::from_str(&content)?;
+        tokens.extend(stored.into_tokens().into_iter().map(|t| ((t.chain_id, t.address), t)));
+        info!("Loaded {} tokens from {}", tokens.len(), path.display());
+    }
+    Ok(tokens)
+}
+
+/// Registry of token metadata resolved from ERC-20 contracts
+///
+/// Cloning a registry shares its cache, so a single instance can be handed
+/// to several feeds.
+#[derive(Debug, Clone)]
+pub struct TokenRegistry {
+    rpc: RpcClient,
+    multicall: Option<Multicall>,
+    cache_path: Option<PathBuf>,
+    chain_id: u64,
+    tokens: Arc<RwLock<HashMap<TokenKey, Token>>>,
+    // Tokens were resolved since the cache file was last written
+    dirty: Arc<AtomicBool>,
+}
+
+impl TokenRegistry {
+    /// Create an in-memory registry resolving through the given RPC endpoint
+    pub fn new(rpc_url: &str) -> Self {
+        TokenRegistry {
+            rpc: RpcClient::new(rpc_url),
+            multicall: None,
+            cache_path: None,
+            chain_id: METIS_CHAIN_ID,
+            tokens: Arc::new(RwLock::new(HashMap::new())),
+            dirty: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Create an in-memory registry for Metis Andromeda, seeded with
+    /// well-known tokens
+    pub fn metis() -> Self {
+        let tokens = known_metis_tokens()
+            .into_iter()
+            .map(|t| ((t.chain_id, t.address), t))
+            .collect();
+
+        TokenRegistry {
+            rpc: RpcClient::new(METIS_RPC_URL),
+            multicall: None,
+            cache_path: None,
+            chain_id: METIS_CHAIN_ID,
+            tokens: Arc::new(RwLock::new(tokens)),
+            dirty: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Create a registry backed by a JSON cache file, loading it if present
+    pub fn with_cache_file(rpc_url: &str, path: impl AsRef<Path>) -> Result<Self, TokenRegistryError> {
+        let path = path.as_ref().to_path_buf();
+        let tokens = load_cache_file(&path)?;
+
+        Ok(TokenRegistry {
+            rpc: RpcClient::new(rpc_url),
+            multicall: None,
+            cache_path: Some(path),
+            chain_id: METIS_CHAIN_ID,
+            tokens: Arc::new(RwLock::new(tokens)),
+            dirty: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Create a Metis Andromeda registry backed by a JSON cache file and
+    /// seeded with well-known tokens the cache does not already hold
+    pub fn metis_with_cache_file(path: impl AsRef<Path>) -> Result<Self, TokenRegistryError> {
+        let path = path.as_ref().to_path_buf();
+        let mut tokens = load_cache_file(&path)?;
+        for token in known_metis_tokens() {
+            tokens.entry((token.chain_id, token.address)).or_insert(token);
+        }
+
+        Ok(TokenRegistry {
+            rpc: RpcClient::new(METIS_RPC_URL),
+            multicall: None,
+            cache_path: Some(path),
+            chain_id: METIS_CHAIN_ID,
+            tokens: Arc::new(RwLock::new(tokens)),
+            dirty: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Chain the RPC endpoint serves, recorded on resolved tokens and used
+    /// for lookups (Metis Andromeda by default)
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    /// Batch metadata lookups in `resolve_many` through Multicall3
+    pub fn with_multicall(mut self, multicall: Multicall) -> Self {
+        self.multicall = Some(multicall);
+        self
+    }
+
+    /// Add tokens whose metadata is already known, on their own chains
+    pub async fn seed(&self, tokens: impl IntoIterator<Item = Token>) {
+        let mut cache = self.tokens.write().await;
+        for token in tokens {
+            cache.entry((token.chain_id, token.address)).or_insert(token);
+        }
+    }
+
+    /// Look up a token on this registry's chain without touching the network
+    pub async fn get(&self, address: Address) -> Option<Token> {
+        self.tokens.read().await.get(&(self.chain_id, address)).cloned()
+    }
+
+    /// Number of tokens currently known
+    pub async fn len(&self) -> usize {
+        self.tokens.read().await.len()
+    }
+
+    /// Whether the registry knows no tokens yet
+    pub async fn is_empty(&self) -> bool {
+        self.tokens.read().await.is_empty()
+    }
+
+    /// Resolve a token's metadata, using the cache when possible
+    ///
+    /// Newly resolved tokens are kept in memory; call `flush` to write them
+    /// to the cache file.
+    pub async fn resolve(&self, address: Address) -> Result<Token, TokenRegistryError> {
+        if let Some(token) = self.get(address).await {
+            return Ok(token);
+        }
+
+        let token = self.fetch_metadata(address).await?;
+        debug!("Resolved token {} ({}, {} decimals)", token.symbol, address, token.decimals);
+
+        self.tokens.write().await.insert((self.chain_id, address), token.clone());
+        self.dirty.store(true, Ordering::SeqCst);
+        Ok(token)
+    }
+
+    /// Write tokens resolved since the last flush to the cache file
+    ///
+    /// A failed write is logged and retried on the next flush; lookups that
+    /// already succeeded are not affected.
+    pub async fn flush(&self) {
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        if let Err(e) = self.persist().await {
+            self.dirty.store(true, Ordering::SeqCst);
+            warn!("Failed to persist token cache: {}", e);
+        }
+    }
+
+    /// Resolve several tokens, skipping (and logging) any that fail, then
+    /// flush the cache file once
+    pub async fn resolve_many(&self, addresses: &[Address]) -> HashMap<Address, Token> {
+        if let Some(multicall) = &self.multicall {
+            self.fetch_missing_batched(multicall, addresses).await;
+        }
+
+        let mut resolved = HashMap::new();
+        for &address in addresses {
+            match self.resolve(address).await {
+                Ok(token) => {
+                    resolved.insert(address, token);
+                }
+                Err(e) => warn!("Could not resolve token {}: {}", address, e),
+            }
+        }
+        self.flush().await;
+        resolved
+    }
+
+    /// Fetch metadata for all unknown addresses in Multicall3 batches
+    ///
+    /// Tokens whose calls fail are left unresolved so `resolve` can retry
+    /// them individually.
+    async fn fetch_missing_batched(&self, multicall: &Multicall, addresses: &[Address]) {
+        let mut missing: Vec<Address> = Vec::new();
+        {
+            let cache = self.tokens.read().await;
+            for address in addresses {
+                if !cache.contains_key(&(self.chain_id, *address)) && !missing.contains(address) {
+                    missing.push(*address);
+                }
+            }
+        }
+        if missing.is_empty() {
+            return;
+        }
+
+        const FIELDS: [[u8; 4]; 3] = [abi::SELECTOR_DECIMALS, abi::SELECTOR_SYMBOL, abi::SELECTOR_NAME];
+        let calls = missing.iter()
+            .enumerate()
+            .flat_map(|(i, address)| {
+                FIELDS.iter().enumerate().map(move |(f, selector)| ((i, f), Call::new(*address, *selector)))
+            })
+            .collect();
+        let mut results = multicall.aggregate(calls, BlockTag::Latest).await;
+
+        let mut fetched = 0;
+        {
+            let mut cache = self.tokens.write().await;
+            for (i, &address) in missing.iter().enumerate() {
+                let decimals = results.remove(&(i, 0))
+                    .and_then(|r| r.ok())
+                    .and_then(|d| abi::word(&d, 0).and_then(abi::decode_u8).ok());
+                let symbol = results.remove(&(i, 1))
+                    .and_then(|r| r.ok())
+                    .and_then(|d| abi::decode_string(&d).ok());
+                let name = results.remove(&(i, 2))
+                    .and_then(|r| r.ok())
+                    .and_then(|d| abi::decode_string(&d).ok());
+
+                if let (Some(decimals), Some(symbol)) = (decimals, symbol) {
+                    let name = name.unwrap_or_else(|| symbol.clone());
+                    let token = Token::new(&symbol, &name, decimals, address).with_chain_id(self.chain_id);
+                    cache.insert((self.chain_id, address), token);
+                    fetched += 1;
+                }
+            }
+        }
+
+        debug!("Resolved {}/{} tokens via multicall", fetched, missing.len());
+        if fetched > 0 {
+            self.dirty.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Read decimals(), symbol() and name() from the token contract
+    async fn fetch_metadata(&self, address: Address) -> Result<Token, TokenRegistryError> {
+        let target = address.to_lowercase_hex();
+        let call = |selector: [u8; 4]| {
+            let target = &target;
+            async move {
+                self.rpc
+                    .eth_call(target, &selector, BlockTag::Latest)
+                    .await
+                    .map_err(|source| TokenRegistryError::Rpc { address, source })
+            }
+        };
+        let abi_err = |source| TokenRegistryError::Abi { address, source };
+
+        let decimals_data = call(abi::SELECTOR_DECIMALS).await?;
+        let decimals = abi::word(&decimals_data, 0)
+            .and_then(abi::decode_u8)
+            .map_err(abi_err)?;
+        let symbol = abi::decode_string(&call(abi::SELECTOR_SYMBOL).await?).map_err(abi_err)?;
+
+        // name() is optional in ERC-20; fall back to the symbol
+        let name = match call(abi::SELECTOR_NAME).await {
+            Ok(data) => abi::decode_string(&data).unwrap_or_else(|_| symbol.clone()),
+            Err(_) => symbol.clone(),
+        };
+
+        Ok(Token::new(&symbol, &name, decimals, address).with_chain_id(self.chain_id))
+    }
+
+    /// Write the cache to disk if this registry is file-backed
+    async fn persist(&self) -> Result<(), TokenRegistryError> {
+        let Some(path) = &self.cache_path else {
+            return Ok(());
+        };
+
+        let content = {
+            let tokens = self.tokens.read().await;
+            let mut stored: Vec<&Token> = tokens.values().collect();
+            stored.sort_by_key(|t| (t.chain_id, t.address));
+            serde_json::to_string_pretty(&stored)?
+        };
+
+        // Write to a sibling file first so a crash never leaves a torn cache
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, content).await?;
+        tokio::fs::rename(&tmp, path).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{abi_string, MockRpcServer};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const USDC: &str = "0xEA32A96608495e54156Ae48931A7c20f0dcc1a21";
+
+    fn usdc() -> Address {
+        USDC.parse().unwrap()
+    }
+
+    async fn mock_token_rpc(calls: Arc<AtomicUsize>) -> MockRpcServer {
+        MockRpcServer::start(move |method, params| {
+            assert_eq!(method, "eth_call");
+            calls.fetch_add(1, Ordering::SeqCst);
+            let data = abi::decode_hex(params[0]["data"].as_str().unwrap()).unwrap();
+            let selector: [u8; 4] = data[..4].try_into().unwrap();
+            match selector {
+                abi::SELECTOR_DECIMALS => Ok(json!(abi::encode_hex(&abi::encode_u128(6)))),
+                abi::SELECTOR_SYMBOL => Ok(json!(abi::encode_hex(&abi_string("m.USDC")))),
+                abi::SELECTOR_NAME => Ok(json!(abi::encode_hex(&abi_string("USD Coin")))),
+                _ => Err((-32000, "execution reverted".to_string())),
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_resolve_caches_metadata() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let server = mock_token_rpc(calls.clone()).await;
+        let registry = TokenRegistry::new(&server.url);
+
+        let token = registry.resolve(usdc()).await.unwrap();
+        assert_eq!(token.symbol, "m.USDC");
+        assert_eq!(token.chain_id, METIS_CHAIN_ID);
+        assert_eq!(token.name, "USD Coin");
+        assert_eq!(token.decimals, 6);
+
+        // Second lookup (different case) is served from cache
+        let calls_after_first = calls.load(Ordering::SeqCst);
+        registry.resolve(USDC.to_lowercase().parse().unwrap()).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), calls_after_first);
+    }
+
+    #[tokio::test]
+    async fn test_cache_file_persists_across_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tokens.json");
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let server = mock_token_rpc(calls.clone()).await;
+        let registry = TokenRegistry::with_cache_file(&server.url, &path).unwrap();
+        registry.resolve(usdc()).await.unwrap();
+        assert!(!path.exists());
+        registry.flush().await;
+        assert!(path.exists());
+
+        // A fresh registry pointed at a dead endpoint still knows the token
+        let reloaded = TokenRegistry::with_cache_file("http://127.0.0.1:1", &path).unwrap();
+        let token = reloaded.resolve(usdc()).await.unwrap();
+        assert_eq!(token.decimals, 6);
+
+        // The Metis registry adds the well-known tokens to what was cached
+        let metis = TokenRegistry::metis_with_cache_file(&path).unwrap();
+        assert_eq!(metis.len().await, known_metis_tokens().len());
+        assert!(metis.get("0x420000000000000000000000000000000000000a".parse().unwrap()).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_unwritable_cache_does_not_fail_lookups() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing").join("tokens.json");
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let server = mock_token_rpc(calls).await;
+        let registry = TokenRegistry::with_cache_file(&server.url, &path).unwrap();
+        let resolved = registry.resolve_many(&[usdc()]).await;
+        assert_eq!(resolved[&usdc()].decimals, 6);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_cache_is_keyed_by_chain() {
+        let registry = TokenRegistry::new("http://127.0.0.1:1");
+        let bridged = Token::new("USDC", "USD Coin", 6, usdc()).with_chain_id(1);
+        registry.seed(known_metis_tokens().into_iter().chain([bridged])).await;
+
+        assert_eq!(registry.get(usdc()).await.unwrap().symbol, "m.USDC");
+        let ethereum = registry.clone().with_chain_id(1);
+        assert_eq!(ethereum.get(usdc()).await.unwrap().symbol, "USDC");
+        let other = registry.clone().with_chain_id(10);
+        assert!(other.get(usdc()).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_many_batches_through_multicall() {
+        use crate::multicall::MULTICALL3_ADDRESS;
+        use crate::test_util::serve_aggregate3;
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let server = MockRpcServer::start(move |_, params| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(params[0]["to"].as_str().unwrap(), MULTICALL3_ADDRESS);
+            let data = abi::decode_hex(params[0]["data"].as_str().unwrap()).unwrap();
+            let response = serve_aggregate3(&data, |_, call| {
+                match <[u8; 4]>::try_from(call).unwrap() {
+                    abi::SELECTOR_DECIMALS => Some(abi::encode_u128(6).to_vec()),
+                    abi::SELECTOR_SYMBOL => Some(abi_string("m.USDT")),
+                    _ => None, // name() reverts
+                }
+            });
+            Ok(json!(abi::encode_hex(&response)))
+        })
+        .await;
+
+        let rpc = RpcClient::new(&server.url);
+        let registry = TokenRegistry::new(&server.url).with_multicall(Multicall::new(rpc));
+        let addresses = vec!["0xbB06DCA3AE6887fAbF931640f67cab3e3a16F4dC".parse().unwrap(), usdc()];
+
+        let resolved = registry.resolve_many(&addresses).await;
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert_eq!(resolved[&usdc()].name, "m.USDT");
+    }
+
+    #[tokio::test]
+    async fn test_seeded_tokens_need_no_rpc() {
+        let registry = TokenRegistry::new("http://127.0.0.1:1");
+        registry.seed(known_metis_tokens()).await;
+        assert_eq!(registry.len().await, TokenRegistry::metis().len().await);
+
+        let weth = registry.resolve("0x420000000000000000000000000000000000000a".parse().unwrap()).await.unwrap();
+        assert_eq!(weth.decimals, 18);
+        assert!(registry.resolve("0x0000000000000000000000000000000000000001".parse().unwrap()).await.is_err());
+    }
+}